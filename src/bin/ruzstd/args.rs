@@ -0,0 +1,500 @@
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "Usage: ruzstd [OPTIONS] [FILE]...
+       ruzstd COMMAND [OPTIONS] [FILE]...
+       ruzstd --train [OPTIONS] FILE... -o DICT
+       unzstd [OPTIONS] [FILE]...    same as ruzstd -d
+       zstdcat [OPTIONS] [FILE]...   same as ruzstd -dcf
+Decompress zstd files. With no FILE, or when FILE is -, read standard input.
+Everything after -- is a FILE, even if it starts with -.
+
+Commands, only recognized as the first argument:
+  decompress, cat, list, test, bench, train, help, version
+                     the same as -d, -dcf, -l, -t, -b, --train, -h and -V
+
+Options:
+  -d, --decompress   decompress (the only supported operation)
+  -l, --list         print information about zstd files, more with -v
+  -t, --test         decompress without writing the output and check checksums
+      --format=FORMAT
+                     print the results of -t as text (default) or json, which
+                     is one JSON object per file and line on standard output
+  -b                 benchmark decompression of the FILEs in memory, with -v
+                     also show the time spent per block type and decoding phase
+  -i SECONDS         run each benchmark for at least SECONDS (default 3)
+  -c, --stdout       write to standard output and keep the input files
+  -o FILE            write the output to FILE
+  -r                 operate recursively on directories
+      --output-dir-mirror=DIR
+                     write the output files into DIR, keeping the directory
+                     structure of the input files
+  -T THREADS         decompress up to THREADS files at the same time, 0 means
+                     one per cpu (default 1)
+  -D DICT            use DICT to decompress, can be given more than once
+      --patch-from=REF
+                     decompress patches made with zstd --patch-from=REF
+      --memory=SIZE  accept frames with a window of up to SIZE, e.g. 512MiB
+  -f, --force        overwrite existing output files and write to a terminal
+  -k, --keep         keep the input files (default)
+      --rm           remove the input files after successful decompression
+      --no-sparse    write zeros to output files instead of leaving holes
+      --streaming    decompress and benchmark through the StreamingDecoder
+                     instead of using the FrameDecoder directly, to test that
+                     code path
+  -q, --quiet        print less, repeat to suppress errors too
+  -v, --verbose      print more
+  -h, --help         print this help
+  -V, --version      print the version
+
+Dictionary training:
+      --train        train a dictionary from the sample FILEs (not supported yet)
+      --maxdict=SIZE maximum size of the trained dictionary (default 112640)
+";
+
+/// Same default as zstd
+pub const DEFAULT_MAX_DICT_SIZE: usize = 110 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Compress,
+    Decompress,
+    List,
+    Test,
+    Bench,
+    Train,
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub mode: Mode,
+    pub stdout: bool,
+    pub output: Option<PathBuf>,
+    pub force: bool,
+    pub remove_source: bool,
+    /// Skip over zeros in output files so they become sparse
+    pub sparse: bool,
+    /// Dictionaries given with -D
+    pub dicts: Vec<PathBuf>,
+    /// Reference file of zstd --patch-from
+    pub patch_from: Option<PathBuf>,
+    /// Biggest window size to accept, None means the library default
+    pub max_window_size: Option<u64>,
+    /// Maximum size of a dictionary created by --train
+    pub max_dict_size: usize,
+    /// Minimum time of a benchmark per file
+    pub bench_seconds: u64,
+    /// 0 = silent, 1 = errors only, 2 = default, 3+ = verbose
+    pub verbosity: u8,
+    pub recursive: bool,
+    pub output_dir_mirror: Option<PathBuf>,
+    /// 0 means one per cpu
+    pub threads: usize,
+    /// Empty means stdin. "-" also means stdin
+    pub files: Vec<String>,
+    pub format: Format,
+    /// Decode with a StreamingDecoder instead of a FrameDecoder
+    pub streaming: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            mode: Mode::Compress,
+            stdout: false,
+            output: None,
+            force: false,
+            remove_source: false,
+            sparse: true,
+            dicts: Vec::new(),
+            patch_from: None,
+            max_window_size: None,
+            max_dict_size: DEFAULT_MAX_DICT_SIZE,
+            bench_seconds: 3,
+            verbosity: 2,
+            recursive: false,
+            output_dir_mirror: None,
+            threads: 1,
+            files: Vec::new(),
+            format: Format::Text,
+            streaming: false,
+        }
+    }
+}
+
+/// Parses the whole command line. The defaults depend on the name the program was called by, unzstd decompresses
+/// and zstdcat decompresses to stdout like zstd does. A command as the first argument selects the mode the same way.
+pub fn parse_command_line(program: &str, args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let program = Path::new(program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    match program {
+        "unzstd" => options.mode = Mode::Decompress,
+        "zstdcat" => cat(&mut options),
+        _ => {}
+    }
+
+    let mut args = args;
+    if let Some((first, rest)) = args.split_first() {
+        let mode = match first.as_str() {
+            "decompress" => Some(Mode::Decompress),
+            "cat" => {
+                cat(&mut options);
+                Some(Mode::Decompress)
+            }
+            "list" => Some(Mode::List),
+            "test" => Some(Mode::Test),
+            "bench" => Some(Mode::Bench),
+            "train" => Some(Mode::Train),
+            "help" => Some(Mode::Help),
+            "version" => Some(Mode::Version),
+            _ => None,
+        };
+        if let Some(mode) = mode {
+            options.mode = mode;
+            args = rest;
+        }
+    }
+    parse_into(options, args)
+}
+
+/// Like zstd -dcf
+fn cat(options: &mut Options) {
+    options.mode = Mode::Decompress;
+    options.stdout = true;
+    options.force = true;
+}
+
+/// Parses the arguments after the command. Short flags can be combined like -dcf
+fn parse_into(mut options: Options, args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let mut only_files = false;
+
+    while let Some(arg) = args.next() {
+        if only_files || arg == "-" || !arg.starts_with('-') {
+            options.files.push(arg.clone());
+            continue;
+        }
+        if arg == "--" {
+            only_files = true;
+            continue;
+        }
+
+        if let Some(long) = arg.strip_prefix("--") {
+            if let Some(size) = long.strip_prefix("maxdict=") {
+                options.max_dict_size = parse_size(size)?;
+                continue;
+            }
+            if let Some(size) = long.strip_prefix("memory=") {
+                options.max_window_size = Some(parse_size(size)? as u64);
+                continue;
+            }
+            if let Some(file) = long.strip_prefix("patch-from=") {
+                options.patch_from = Some(PathBuf::from(file));
+                continue;
+            }
+            if let Some(dir) = long.strip_prefix("output-dir-mirror=") {
+                options.output_dir_mirror = Some(PathBuf::from(dir));
+                continue;
+            }
+            if let Some(format) = long.strip_prefix("format=") {
+                options.format = parse_format(format)?;
+                continue;
+            }
+            if let Some(threads) = long.strip_prefix("threads=") {
+                options.threads = parse_threads(threads)?;
+                continue;
+            }
+            match long {
+                "decompress" | "uncompress" => options.mode = Mode::Decompress,
+                "list" => options.mode = Mode::List,
+                "test" => options.mode = Mode::Test,
+                "format" => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| "option --format requires a format".to_owned())?;
+                    options.format = parse_format(format)?;
+                }
+                "train" => options.mode = Mode::Train,
+                "maxdict" => {
+                    let size = args
+                        .next()
+                        .ok_or_else(|| "option --maxdict requires a size".to_owned())?;
+                    options.max_dict_size = parse_size(size)?;
+                }
+                "patch-from" => {
+                    let file = args
+                        .next()
+                        .ok_or_else(|| "option --patch-from requires a file name".to_owned())?;
+                    options.patch_from = Some(PathBuf::from(file));
+                }
+                "output-dir-mirror" => {
+                    let dir = args.next().ok_or_else(|| {
+                        "option --output-dir-mirror requires a directory".to_owned()
+                    })?;
+                    options.output_dir_mirror = Some(PathBuf::from(dir));
+                }
+                "stdout" => options.stdout = true,
+                "force" => options.force = true,
+                "keep" => options.remove_source = false,
+                "rm" => options.remove_source = true,
+                "streaming" => options.streaming = true,
+                "sparse" => options.sparse = true,
+                "no-sparse" => options.sparse = false,
+                "quiet" => options.verbosity = options.verbosity.saturating_sub(1),
+                "verbose" => options.verbosity += 1,
+                "help" => options.mode = Mode::Help,
+                "version" => options.mode = Mode::Version,
+                _ => return Err(format!("unknown option: {}", arg)),
+            }
+            continue;
+        }
+
+        let flags: Vec<char> = arg[1..].chars().collect();
+        for (idx, flag) in flags.iter().enumerate() {
+            match flag {
+                'd' => options.mode = Mode::Decompress,
+                'l' => options.mode = Mode::List,
+                't' => options.mode = Mode::Test,
+                'b' => options.mode = Mode::Bench,
+                'c' => options.stdout = true,
+                'f' => options.force = true,
+                'k' => options.remove_source = false,
+                'r' => options.recursive = true,
+                'q' => options.verbosity = options.verbosity.saturating_sub(1),
+                'v' => options.verbosity += 1,
+                'h' | 'H' => options.mode = Mode::Help,
+                'V' => options.mode = Mode::Version,
+                'o' | 'D' | 'i' | 'T' => {
+                    // the value is either the rest of this argument (-oFILE) or the next argument
+                    let rest: String = flags[idx + 1..].iter().collect();
+                    let value = if rest.is_empty() {
+                        args.next()
+                            .cloned()
+                            .ok_or_else(|| format!("option -{} requires a value", flag))?
+                    } else {
+                        rest
+                    };
+                    match flag {
+                        'o' => options.output = Some(PathBuf::from(value)),
+                        'D' => options.dicts.push(PathBuf::from(value)),
+                        'T' => options.threads = parse_threads(&value)?,
+                        _ => {
+                            options.bench_seconds = value
+                                .parse()
+                                .map_err(|_| format!("invalid number of seconds: {}", value))?
+                        }
+                    }
+                    break;
+                }
+                _ => return Err(format!("unknown option: -{}", flag)),
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    match format {
+        "text" => Ok(Format::Text),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("unknown format: {}, use text or json", format)),
+    }
+}
+
+fn parse_threads(threads: &str) -> Result<usize, String> {
+    threads
+        .parse()
+        .map_err(|_| format!("invalid number of threads: {}", threads))
+}
+
+/// Parses sizes like 4096, 100K, 1MiB or 2G
+fn parse_size(size: &str) -> Result<usize, String> {
+    let digits = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, suffix) = size.split_at(digits);
+    let number: usize = number
+        .parse()
+        .map_err(|_| format!("invalid size: {}", size))?;
+    let shift = match suffix {
+        "" => 0,
+        "K" | "KB" | "KiB" => 10,
+        "M" | "MB" | "MiB" => 20,
+        "G" | "GB" | "GiB" => 30,
+        _ => return Err(format!("invalid size: {}", size)),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("invalid size: {}", size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_as(program: &str, args: &str) -> Result<Options, String> {
+        let args: Vec<String> = args.split_whitespace().map(|s| s.to_owned()).collect();
+        parse_command_line(program, &args)
+    }
+
+    fn parse_str(args: &str) -> Result<Options, String> {
+        parse_as("ruzstd", args)
+    }
+
+    #[test]
+    fn program_name() {
+        assert_eq!(parse_as("ruzstd", "a.zst").unwrap().mode, Mode::Compress);
+        let options = parse_as("/usr/bin/unzstd", "a.zst").unwrap();
+        assert_eq!(options.mode, Mode::Decompress);
+        assert!(!options.stdout);
+        let options = parse_as("zstdcat", "a.zst").unwrap();
+        assert_eq!(options.mode, Mode::Decompress);
+        assert!(options.stdout && options.force);
+        // flags still override what the name selected
+        assert_eq!(parse_as("unzstd", "-l a.zst").unwrap().mode, Mode::List);
+    }
+
+    #[test]
+    fn commands() {
+        let options = parse_as("ruzstd", "list -v a.zst").unwrap();
+        assert_eq!(options.mode, Mode::List);
+        assert_eq!(options.files, vec!["a.zst"]);
+        let options = parse_as("ruzstd", "cat a.zst").unwrap();
+        assert!(options.stdout && options.force);
+        assert_eq!(
+            parse_as("ruzstd", "test --streaming").unwrap().mode,
+            Mode::Test
+        );
+        assert!(parse_as("ruzstd", "test --streaming").unwrap().streaming);
+        // only the first argument can be a command
+        let options = parse_as("ruzstd", "-d list").unwrap();
+        assert_eq!(options.mode, Mode::Decompress);
+        assert_eq!(options.files, vec!["list"]);
+    }
+
+    #[test]
+    fn double_dash() {
+        let options = parse_as("unzstd", "-c -- -a.zst --rm").unwrap();
+        assert!(options.stdout);
+        assert!(!options.remove_source);
+        assert_eq!(options.files, vec!["-a.zst", "--rm"]);
+    }
+
+    #[test]
+    fn combined_flags() {
+        let options = parse_str("-dcf a.zst - b.zst").unwrap();
+        assert_eq!(options.mode, Mode::Decompress);
+        assert!(options.stdout);
+        assert!(options.force);
+        assert_eq!(options.files, vec!["a.zst", "-", "b.zst"]);
+    }
+
+    #[test]
+    fn list_mode() {
+        let options = parse_str("-lv a.zst").unwrap();
+        assert_eq!(options.mode, Mode::List);
+        assert_eq!(options.verbosity, 3);
+        assert_eq!(parse_str("--list").unwrap().mode, Mode::List);
+    }
+
+    #[test]
+    fn output_file() {
+        assert_eq!(
+            parse_str("-d -o out a.zst").unwrap().output,
+            Some(PathBuf::from("out"))
+        );
+        assert_eq!(
+            parse_str("-doout a.zst").unwrap().output,
+            Some(PathBuf::from("out"))
+        );
+        assert!(parse_str("-d -o").is_err());
+    }
+
+    #[test]
+    fn dictionaries() {
+        let options = parse_str("-d -D a.dict -Db.dict x.zst").unwrap();
+        assert_eq!(
+            options.dicts,
+            vec![PathBuf::from("a.dict"), PathBuf::from("b.dict")]
+        );
+        assert_eq!(options.files, vec!["x.zst"]);
+        assert!(parse_str("-d -D").is_err());
+
+        let options = parse_str("--train s1 s2 -o dict --maxdict=100K").unwrap();
+        assert_eq!(options.mode, Mode::Train);
+        assert_eq!(options.max_dict_size, 100 * 1024);
+        assert_eq!(options.output, Some(PathBuf::from("dict")));
+        assert_eq!(parse_str("--maxdict 4096").unwrap().max_dict_size, 4096);
+        assert!(parse_str("--maxdict=12X").is_err());
+    }
+
+    #[test]
+    fn patch_from() {
+        let options = parse_str("-d --patch-from=old --memory=2GiB a.zst").unwrap();
+        assert_eq!(options.patch_from, Some(PathBuf::from("old")));
+        assert_eq!(options.max_window_size, Some(2 << 30));
+        assert_eq!(
+            parse_str("-d --patch-from old").unwrap().patch_from,
+            Some(PathBuf::from("old"))
+        );
+        assert!(parse_str("--patch-from").is_err());
+    }
+
+    #[test]
+    fn recursive() {
+        let options = parse_str("-drT4 --output-dir-mirror out dir").unwrap();
+        assert!(options.recursive);
+        assert_eq!(options.threads, 4);
+        assert_eq!(options.output_dir_mirror, Some(PathBuf::from("out")));
+        assert_eq!(options.files, vec!["dir"]);
+        let options = parse_str("-r --threads=0 --output-dir-mirror=out").unwrap();
+        assert_eq!(options.threads, 0);
+        assert_eq!(options.output_dir_mirror, Some(PathBuf::from("out")));
+        assert!(parse_str("-T x").is_err());
+    }
+
+    #[test]
+    fn test_mode() {
+        let options = parse_str("-t --format json a.zst").unwrap();
+        assert_eq!(options.mode, Mode::Test);
+        assert_eq!(options.format, Format::Json);
+        assert_eq!(options.files, vec!["a.zst"]);
+        assert_eq!(parse_str("--test").unwrap().format, Format::Text);
+        assert_eq!(parse_str("--format=json").unwrap().format, Format::Json);
+        assert!(parse_str("--format=xml").is_err());
+    }
+
+    #[test]
+    fn bench_mode() {
+        let options = parse_str("-b -i1 a.zst").unwrap();
+        assert_eq!(options.mode, Mode::Bench);
+        assert_eq!(options.bench_seconds, 1);
+        assert_eq!(parse_str("-bvi 5").unwrap().bench_seconds, 5);
+        assert!(parse_str("-b -ix").is_err());
+    }
+
+    #[test]
+    fn verbosity_and_removal() {
+        let options = parse_str("-d -qq --rm -- -file.zst").unwrap();
+        assert_eq!(options.verbosity, 0);
+        assert!(options.remove_source);
+        assert_eq!(options.files, vec!["-file.zst"]);
+        assert_eq!(parse_str("-vv --rm -k").unwrap().verbosity, 4);
+        assert!(!parse_str("--rm -k").unwrap().remove_source);
+        assert!(parse_str("-d").unwrap().sparse);
+        assert!(!parse_str("-d --no-sparse").unwrap().sparse);
+        assert!(parse_str("-x").is_err());
+        assert!(parse_str("--nope").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::decompress;
+use ruzstd::decoding::timings::{BlockTimings, BlockTypeStats};
+use ruzstd::frame_decoder::FrameDecoder;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+pub struct BenchResult {
+    pub compressed_size: u64,
+    pub decompressed_size: u64,
+    pub iterations: u32,
+    pub total_time: Duration,
+    pub fastest: Duration,
+    /// Summed over all iterations
+    pub timings: Option<BlockTimings>,
+}
+
+/// Decodes data into nothing again and again until at least min_time has passed
+pub fn run(
+    data: &[u8],
+    frame_dec: &mut FrameDecoder,
+    min_time: Duration,
+    with_timings: bool,
+    streaming: bool,
+) -> Result<BenchResult, String> {
+    frame_dec.set_block_timings(with_timings);
+
+    let mut result = BenchResult {
+        compressed_size: data.len() as u64,
+        decompressed_size: 0,
+        iterations: 0,
+        total_time: Duration::default(),
+        fastest: Duration::MAX,
+        timings: None,
+    };
+    while result.iterations == 0 || result.total_time < min_time {
+        let start = Instant::now();
+        result.decompressed_size =
+            decompress::decode_stream(&mut &data[..], &mut std::io::sink(), frame_dec, streaming)?;
+        let elapsed = start.elapsed();
+
+        result.iterations += 1;
+        result.total_time += elapsed;
+        result.fastest = result.fastest.min(elapsed);
+    }
+    result.timings = frame_dec.block_timings();
+    frame_dec.set_block_timings(false);
+    Ok(result)
+}
+
+/// MB/s with MB = 1,000,000 bytes like zstd -b
+fn speed(bytes: u64, time: Duration) -> f64 {
+    if time.as_secs_f64() == 0.0 {
+        0.0
+    } else {
+        bytes as f64 / 1_000_000.0 / time.as_secs_f64()
+    }
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+pub fn print_result(out: &mut dyn Write, name: &str, result: &BenchResult) -> std::io::Result<()> {
+    let average = result.total_time / result.iterations;
+    writeln!(
+        out,
+        "{:<20}: {:>10} -> {:>10} ({:.3}), {:>8.1} MB/s, {} iterations, fastest {:.3} ms, average {:.3} ms",
+        name,
+        result.compressed_size,
+        result.decompressed_size,
+        result.decompressed_size as f64 / result.compressed_size.max(1) as f64,
+        speed(result.decompressed_size, result.fastest),
+        result.iterations,
+        millis(result.fastest),
+        millis(average)
+    )?;
+
+    if let Some(timings) = &result.timings {
+        print_timings(out, timings, result.iterations)?;
+    }
+    Ok(())
+}
+
+/// Prints where the time went, per iteration
+fn print_timings(
+    out: &mut dyn Write,
+    timings: &BlockTimings,
+    iterations: u32,
+) -> std::io::Result<()> {
+    let total = timings.total_time();
+    let share = |time: Duration| {
+        if total.as_nanos() == 0 {
+            0.0
+        } else {
+            time.as_secs_f64() / total.as_secs_f64() * 100.0
+        }
+    };
+
+    let block_types: [(&str, &BlockTypeStats); 3] = [
+        ("raw", &timings.raw),
+        ("rle", &timings.rle),
+        ("compressed", &timings.compressed),
+    ];
+    for (name, stats) in block_types.iter() {
+        writeln!(
+            out,
+            "    {:<20} {:>8} blocks {:>12} bytes {:>10.3} ms {:>5.1}% {:>10.1} MB/s",
+            name,
+            stats.blocks / u64::from(iterations),
+            stats.decompressed_bytes / u64::from(iterations),
+            millis(stats.time / iterations),
+            share(stats.time),
+            speed(stats.decompressed_bytes, stats.time)
+        )?;
+    }
+
+    let phases = [
+        ("literals decoding", timings.literals_decoding),
+        ("sequence decoding", timings.sequence_decoding),
+        ("sequence execution", timings.sequence_execution),
+    ];
+    for (name, time) in phases.iter() {
+        writeln!(
+            out,
+            "      {:<18} {:>40.3} ms {:>5.1}%",
+            name,
+            millis(*time / iterations),
+            share(*time)
+        )?;
+    }
+    Ok(())
+}
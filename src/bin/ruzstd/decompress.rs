@@ -0,0 +1,250 @@
+use crate::args::Options;
+use ruzstd::decoding::dictionary::Dictionary;
+use ruzstd::errors::FrameDecoderError;
+use ruzstd::frame::{self, FrameStart, FrameStartError};
+use ruzstd::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+use ruzstd::StreamingDecoder;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 1024 * 1024;
+
+/// Suffixes that are stripped to get the output name and what they are replaced with
+const SUFFIXES: &[(&str, &str)] = &[(".zst", ""), (".tzst", ".tar")];
+
+/// The name of the decompressed file or None if the input does not have a known suffix
+pub fn output_name(input: &str) -> Option<PathBuf> {
+    for (suffix, replacement) in SUFFIXES {
+        if let Some(stem) = input.strip_suffix(suffix) {
+            if !stem.is_empty() && !stem.ends_with(std::path::MAIN_SEPARATOR) {
+                return Some(PathBuf::from(format!("{}{}", stem, replacement)));
+            }
+        }
+    }
+    None
+}
+
+/// The dictionaries, --patch-from reference and window limit from the command line, loaded once and shared by the
+/// decoders of all threads
+pub struct DecoderSettings {
+    dicts: Vec<Arc<Dictionary>>,
+    prefix: Option<Arc<[u8]>>,
+    max_window_size: Option<u64>,
+}
+
+impl DecoderSettings {
+    /// Errors name the file that could not be loaded
+    pub fn load(options: &Options) -> Result<DecoderSettings, String> {
+        let prefix = match &options.patch_from {
+            Some(reference) => Some(
+                std::fs::read(reference)
+                    .map_err(|e| format!("{}: {}", reference.display(), e))?
+                    .into(),
+            ),
+            None => None,
+        };
+        let dicts = options
+            .dicts
+            .iter()
+            .map(|dict| load_dict(dict).map_err(|m| format!("{}: {}", dict.display(), m)))
+            .collect::<Result<_, _>>()?;
+        Ok(DecoderSettings {
+            dicts,
+            prefix,
+            max_window_size: options.max_window_size,
+        })
+    }
+
+    pub fn frame_decoder(&self) -> FrameDecoder {
+        let mut frame_dec = FrameDecoder::new();
+        if let Some(max_window_size) = self.max_window_size {
+            frame_dec.set_max_window_size(max_window_size);
+        }
+        if let Some(prefix) = &self.prefix {
+            frame_dec.set_prefix(Arc::clone(prefix));
+        }
+        for dict in &self.dicts {
+            frame_dec.add_shared_dict(Arc::clone(dict));
+        }
+        frame_dec
+    }
+}
+
+fn load_dict(path: &Path) -> Result<Arc<Dictionary>, String> {
+    let raw = std::fs::read(path).map_err(|e| e.to_string())?;
+    Dictionary::decode_dict(&raw)
+        .map(Arc::new)
+        .map_err(|m| format!("not a valid dictionary: {}", m))
+}
+
+/// Decodes all frames in input into output and skips skippable frames. Checksums are verified.
+/// With streaming the frames are read through a StreamingDecoder instead of driving frame_dec directly, so both
+/// ways of using the library can be tested from the command line.
+/// Returns how many bytes were written to output.
+pub fn decode_stream(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    frame_dec: &mut FrameDecoder,
+    streaming: bool,
+) -> Result<u64, String> {
+    let mut written = 0;
+    let mut read_any_frame = false;
+    // index of the zstd frame being decoded, skippable frames are not counted just like in the output of -lv
+    let mut frame_index = 0;
+
+    loop {
+        let magic = match frame::read_frame_start(input) {
+            Ok(FrameStart::End) if read_any_frame => break,
+            Ok(FrameStart::End) => return Err("unexpected end of file, input is empty".to_owned()),
+            Ok(FrameStart::Frame { magic }) => magic,
+            Ok(FrameStart::Skippable { data_size, .. }) => {
+                let skipped = frame::skip_skippable_data(input, data_size)
+                    .map_err(|e| format!("read error: {}", e))?;
+                if skipped != u64::from(data_size) {
+                    return Err("unexpected end of file in skippable frame".to_owned());
+                }
+                read_any_frame = true;
+                continue;
+            }
+            Err(FrameStartError::Io(e)) => return Err(format!("read error: {}", e)),
+            Err(FrameStartError::TrailingBytes(_)) => {
+                return Err(
+                    "unexpected end of file, trailing garbage after the last frame".to_owned(),
+                )
+            }
+            Err(FrameStartError::TruncatedSkippableHeader) => {
+                return Err("unexpected end of file in skippable frame".to_owned())
+            }
+        };
+        read_any_frame = true;
+
+        frame_dec.reset(&mut (&magic[..]).chain(&mut *input))?;
+        if streaming {
+            written += decode_streaming(input, output, frame_dec, frame_index)?;
+        }
+        while !frame_dec.is_finished() {
+            frame_dec
+                .decode_blocks(input, BlockDecodingStrategy::UptoBytes(BATCH_SIZE))
+                .map_err(|e| decoder_error(&e, frame_index))?;
+            written += frame_dec
+                .collect_to_writer(output)
+                .map_err(|e| format!("write error: {}", e))? as u64;
+        }
+        written += frame_dec
+            .collect_to_writer(output)
+            .map_err(|e| format!("write error: {}", e))? as u64;
+
+        if let Some(chksum) = frame_dec.get_checksum_from_data() {
+            let calculated = frame_dec.get_calculated_checksum().unwrap_or(0);
+            if chksum != calculated {
+                return Err(format!(
+                    "checksum mismatch in frame {}: expected {:08x}, calculated {:08x}",
+                    frame_index, chksum, calculated
+                ));
+            }
+        }
+        frame_index += 1;
+    }
+
+    Ok(written)
+}
+
+/// The message for a failed frame, the same for both ways of decoding
+fn decoder_error(e: &FrameDecoderError, frame_index: usize) -> String {
+    match e {
+        FrameDecoderError::DictNotProvided { dict_id } => format!(
+            "frame {} needs the dictionary with id {}, provide it with -D",
+            frame_index, dict_id
+        ),
+        e => e.to_string(),
+    }
+}
+
+/// Decodes the rest of the frame frame_dec was reset for with a StreamingDecoder. frame_dec is put back in place
+/// even if this fails, so its settings are kept for the next file
+fn decode_streaming(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    frame_dec: &mut FrameDecoder,
+    frame_index: usize,
+) -> Result<u64, String> {
+    let decoder = std::mem::replace(frame_dec, FrameDecoder::new());
+    let mut stream = StreamingDecoder::from_initialized(input, decoder);
+    let mut buf = vec![0u8; BATCH_SIZE];
+    let mut written = 0;
+    let result = loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break Ok(written),
+            Ok(n) => {
+                if let Err(e) = output.write_all(&buf[..n]) {
+                    break Err(format!("write error: {}", e));
+                }
+                written += n as u64;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                // errors of the decoder are wrapped into the io::Error by the StreamingDecoder
+                let inner = e
+                    .get_ref()
+                    .and_then(|e| e.downcast_ref::<FrameDecoderError>());
+                break Err(match inner {
+                    Some(inner) => decoder_error(inner, frame_index),
+                    None => e.to_string(),
+                });
+            }
+        }
+    };
+    *frame_dec = stream.inner();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A skippable frame in front, it must not be counted when frames are numbered
+    fn with_skippable_frame(frames: &[&[u8]]) -> Vec<u8> {
+        let mut input = vec![0x50, 0x2A, 0x4D, 0x18, 4, 0, 0, 0, 1, 2, 3, 4];
+        for frame in frames {
+            input.extend_from_slice(frame);
+        }
+        input
+    }
+
+    #[test]
+    fn frame_numbers_in_errors() {
+        let dict_frame = std::fs::read("./dict_tests/files/ModemManager.service.zst").unwrap();
+        let frame = std::fs::read("./decodecorpus_files/z000000.zst").unwrap();
+        let input = with_skippable_frame(&[&frame, &dict_frame]);
+        let mut broken_checksum = frame.clone();
+        *broken_checksum.last_mut().unwrap() ^= 1;
+        let corrupted = with_skippable_frame(&[&frame, &broken_checksum]);
+
+        for streaming in [false, true] {
+            let mut frame_dec = FrameDecoder::new();
+            let m = decode_stream(
+                &mut input.as_slice(),
+                &mut std::io::sink(),
+                &mut frame_dec,
+                streaming,
+            )
+            .unwrap_err();
+            assert!(
+                m.starts_with("frame 1 needs the dictionary with id "),
+                "{}",
+                m
+            );
+            assert!(m.ends_with(", provide it with -D"), "{}", m);
+
+            let m = decode_stream(
+                &mut corrupted.as_slice(),
+                &mut std::io::sink(),
+                &mut frame_dec,
+                streaming,
+            )
+            .unwrap_err();
+            assert!(m.starts_with("checksum mismatch in frame 1:"), "{}", m);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::args::Options;
+use crate::decompress;
+use crate::Log;
+use std::fs::{File, FileTimes, Metadata};
+use std::path::{Component, Path, PathBuf};
+
+/// The files given on the command line. With -r directories are replaced by all files with a known suffix below them.
+/// Errors while walking are reported right away, the bool is false if there were any.
+pub fn input_files(options: &Options, log: &Log) -> (Vec<String>, bool) {
+    let mut files = Vec::new();
+    let mut ok = true;
+    for file in &options.files {
+        if options.recursive && file != "-" && Path::new(file).is_dir() {
+            ok &= walk(Path::new(file), &mut files, log);
+        } else {
+            files.push(file.clone());
+        }
+    }
+    (files, ok)
+}
+
+/// Collects all files with a known suffix below dir in a stable order. Symlinks are not followed.
+/// Returns false if anything could not be read
+fn walk(dir: &Path, files: &mut Vec<String>, log: &Log) -> bool {
+    let entries = std::fs::read_dir(dir).and_then(|entries| entries.collect::<Result<Vec<_>, _>>());
+    let mut entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            log.error(&format!("{}: {}", dir.display(), e));
+            return false;
+        }
+    };
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut ok = true;
+    for entry in entries {
+        let path = entry.path();
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => {
+                log.error(&format!("{}: {}", path.display(), e));
+                ok = false;
+                continue;
+            }
+        };
+        if file_type.is_dir() {
+            ok &= walk(&path, files, log);
+        } else if file_type.is_file() {
+            match path.to_str() {
+                Some(name) if decompress::output_name(name).is_some() => {
+                    files.push(name.to_owned())
+                }
+                Some(_) => {}
+                None => {
+                    log.error(&format!(
+                        "{}: file name is not valid UTF-8 -- ignored",
+                        path.display()
+                    ));
+                    ok = false;
+                }
+            }
+        }
+    }
+    ok
+}
+
+/// Where the output for path goes with --output-dir-mirror=dir. Leading /, . and .. are dropped, so the result is
+/// always inside dir
+pub fn mirror_path(dir: &Path, path: &Path) -> PathBuf {
+    let relative: PathBuf = path
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    dir.join(relative)
+}
+
+/// Gives output the timestamps, owner and permissions of the input, like zstd does. Errors are ignored, e.g. only
+/// root may change the owner
+pub fn copy_metadata(input: &Metadata, output: &File) {
+    if let (Ok(accessed), Ok(modified)) = (input.accessed(), input.modified()) {
+        let _ = output.set_times(
+            FileTimes::new()
+                .set_accessed(accessed)
+                .set_modified(modified),
+        );
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = std::os::unix::fs::fchown(output, Some(input.uid()), Some(input.gid()));
+    }
+    // after changing the owner, which can clear the setuid bit
+    let _ = output.set_permissions(input.permissions());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirror() {
+        let dir = Path::new("out");
+        assert_eq!(
+            mirror_path(dir, Path::new("a/b.txt")),
+            Path::new("out/a/b.txt")
+        );
+        assert_eq!(
+            mirror_path(dir, Path::new("/a/./b.txt")),
+            Path::new("out/a/b.txt")
+        );
+        assert_eq!(
+            mirror_path(dir, Path::new("../../b.txt")),
+            Path::new("out/b.txt")
+        );
+    }
+}
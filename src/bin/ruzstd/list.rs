@@ -0,0 +1,276 @@
+use ruzstd::inspect::{self, FrameInfo, InspectedFrame};
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom, Write};
+
+/// What --list found out about one file
+#[derive(Default)]
+pub struct FileInfo {
+    pub frames: Vec<FrameInfo>,
+    pub skippable_frames: usize,
+    /// Size of the whole file
+    pub compressed_size: u64,
+}
+
+impl FileInfo {
+    pub fn read(path: &str) -> Result<FileInfo, String> {
+        if path == "-" {
+            return Err("--list does not support reading from standard input".to_owned());
+        }
+        let f = File::open(path).map_err(|e| e.to_string())?;
+        let compressed_size = f.metadata().map_err(|e| e.to_string())?.len();
+        let mut reader = BufReader::new(f);
+        let mut frames = inspect::inspect(&mut reader, false)?;
+        // the blocks are only needed to add up the size of frames that do not have it in their header
+        let size_missing = frames
+            .iter()
+            .any(|f| matches!(f, InspectedFrame::Zstd(f) if f.content_size.is_none()));
+        if size_missing {
+            reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+            frames = inspect::inspect(&mut reader, true)?;
+        }
+
+        let mut info = FileInfo {
+            compressed_size,
+            ..FileInfo::default()
+        };
+        for frame in frames {
+            match frame {
+                InspectedFrame::Zstd(frame) => info.frames.push(frame),
+                InspectedFrame::Skippable(_) => info.skippable_frames += 1,
+            }
+        }
+        if info.frames.is_empty() {
+            return Err("file does not contain any zstd frames".to_owned());
+        }
+        Ok(info)
+    }
+
+    /// None if any frame does not know its size
+    pub fn decompressed_size(&self) -> Option<u64> {
+        self.frames.iter().map(|f| f.decompressed_size()).sum()
+    }
+
+    /// Window size of the last frame, which is what zstd -lv reports
+    pub fn window_size(&self) -> u64 {
+        self.frames.last().map(|f| f.window_size).unwrap_or(0)
+    }
+
+    pub fn check(&self) -> &'static str {
+        check_name(self.frames.iter().map(|f| f.checksum_flag))
+    }
+}
+
+fn check_name(mut flags: impl Iterator<Item = bool> + Clone) -> &'static str {
+    if flags.clone().all(|f| f) {
+        "XXH64"
+    } else if flags.any(|f| f) {
+        "Mixed"
+    } else {
+        "None"
+    }
+}
+
+/// Splits a size into number and unit like zstd does: bytes below 1 KiB, else the biggest fitting binary unit
+/// with fewer decimals for bigger numbers
+fn human_parts(size: u64) -> (String, &'static str) {
+    const UNITS: &[&str] = &[" KiB", " MiB", " GiB", " TiB", " PiB"];
+    if size < 1024 {
+        return (size.to_string(), " B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let precision = if value >= 100.0 {
+        0
+    } else if value >= 10.0 {
+        1
+    } else {
+        2
+    };
+    (format!("{:.*}", precision, value), UNITS[unit])
+}
+
+pub fn human_size(size: u64) -> String {
+    let (value, unit) = human_parts(size);
+    format!("{}{}", value, unit)
+}
+
+fn ratio(compressed: u64, decompressed: u64) -> f64 {
+    if compressed == 0 {
+        0.0
+    } else {
+        decompressed as f64 / compressed as f64
+    }
+}
+
+/// Prints one line per file and a total if there is more than one file, like zstd -l
+pub fn print_summary(out: &mut dyn Write, files: &[(String, FileInfo)]) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "Frames  Skips  Compressed  Uncompressed  Ratio  Check  Filename"
+    )?;
+    for (name, info) in files {
+        print_summary_line(
+            out,
+            info.frames.len() + info.skippable_frames,
+            info.skippable_frames,
+            info.compressed_size,
+            info.decompressed_size(),
+            info.check(),
+            name,
+        )?;
+    }
+
+    if files.len() > 1 {
+        let decompressed: Option<u64> = files.iter().map(|(_, i)| i.decompressed_size()).sum();
+        writeln!(
+            out,
+            "----------------------------------------------------------------- "
+        )?;
+        print_summary_line(
+            out,
+            files
+                .iter()
+                .map(|(_, i)| i.frames.len() + i.skippable_frames)
+                .sum(),
+            files.iter().map(|(_, i)| i.skippable_frames).sum(),
+            files.iter().map(|(_, i)| i.compressed_size).sum(),
+            decompressed,
+            check_name(
+                files
+                    .iter()
+                    .flat_map(|(_, i)| i.frames.iter().map(|f| f.checksum_flag)),
+            ),
+            &format!("{} files", files.len()),
+        )?;
+    }
+    Ok(())
+}
+
+fn print_summary_line(
+    out: &mut dyn Write,
+    frames: usize,
+    skips: usize,
+    compressed: u64,
+    decompressed: Option<u64>,
+    check: &str,
+    name: &str,
+) -> std::io::Result<()> {
+    let (compressed_value, compressed_unit) = human_parts(compressed);
+    match decompressed {
+        Some(decompressed) => {
+            let (value, unit) = human_parts(decompressed);
+            writeln!(
+                out,
+                "{:>6}  {:>5}  {:>6}{:>4}  {:>8}{:>4}  {:>5.3}  {:>5}  {}",
+                frames,
+                skips,
+                compressed_value,
+                compressed_unit,
+                value,
+                unit,
+                ratio(compressed, decompressed),
+                check,
+                name
+            )?;
+        }
+        None => writeln!(
+            out,
+            "{:>6}  {:>5}  {:>6}{:>4}                       {:>5}  {}",
+            frames, skips, compressed_value, compressed_unit, check, name
+        )?,
+    }
+    Ok(())
+}
+
+/// Prints everything about one file, including one line per frame. This is -lv
+pub fn print_verbose(out: &mut dyn Write, name: &str, info: &FileInfo) -> std::io::Result<()> {
+    writeln!(out, "{} ", name)?;
+    writeln!(out, "# Zstandard Frames: {}", info.frames.len())?;
+    if info.skippable_frames > 0 {
+        writeln!(out, "# Skippable Frames: {}", info.skippable_frames)?;
+    }
+    writeln!(out, "DictID: {}", info.frames[0].dict_id.unwrap_or(0))?;
+    let window_size = info.window_size();
+    writeln!(
+        out,
+        "Window Size: {} ({} B)",
+        human_size(window_size),
+        window_size
+    )?;
+    writeln!(
+        out,
+        "Compressed Size: {} ({} B)",
+        human_size(info.compressed_size),
+        info.compressed_size
+    )?;
+    if let Some(decompressed) = info.decompressed_size() {
+        writeln!(
+            out,
+            "Decompressed Size: {} ({} B)",
+            human_size(decompressed),
+            decompressed
+        )?;
+        writeln!(
+            out,
+            "Ratio: {:.4}",
+            ratio(info.compressed_size, decompressed)
+        )?;
+    }
+    match (info.frames.len(), info.frames[0].checksum) {
+        (1, Some(checksum)) => writeln!(out, "Check: {} {:08x}", info.check(), checksum)?,
+        _ => writeln!(out, "Check: {}", info.check())?,
+    }
+    writeln!(out)?;
+
+    writeln!(
+        out,
+        "{:>6}  {:>12}  {:>6}  {:>10}  {:>10}  {:>10}  {:>12}  {:>8}",
+        "Frame", "Offset", "Blocks", "DictID", "Window", "Compressed", "Decompressed", "Check"
+    )?;
+    for (idx, frame) in info.frames.iter().enumerate() {
+        let decompressed = frame
+            .decompressed_size()
+            .map(human_size)
+            .unwrap_or_default();
+        let check = frame
+            .checksum
+            .map(|c| format!("{:08x}", c))
+            .unwrap_or_else(|| "None".to_owned());
+        writeln!(
+            out,
+            "{:>6}  {:>12}  {:>6}  {:>10}  {:>10}  {:>10}  {:>12}  {:>8}",
+            idx,
+            frame.offset,
+            frame.block_count,
+            frame.dict_id.unwrap_or(0),
+            human_size(frame.window_size),
+            human_size(frame.compressed_size),
+            decompressed,
+            check
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_only_without_content_size() {
+        let info = FileInfo::read("./decodecorpus_files/z000000.zst").unwrap();
+        assert!(info.frames[0].blocks.is_empty());
+        assert_eq!(info.decompressed_size(), Some(224787));
+
+        let info = FileInfo::read("./decodecorpus_files/z000001.zst").unwrap();
+        assert!(info.frames[0].content_size.is_none());
+        assert_eq!(info.frames[0].blocks.len(), info.frames[0].block_count);
+        let original = std::fs::metadata("./decodecorpus_files/z000001").unwrap();
+        assert_eq!(info.decompressed_size(), Some(original.len()));
+    }
+}
@@ -0,0 +1,569 @@
+mod args;
+mod bench;
+mod decompress;
+mod files;
+mod list;
+mod progress;
+mod report;
+mod sparse;
+
+use args::{Format, Mode, Options};
+use decompress::DecoderSettings;
+use ruzstd::frame_decoder::FrameDecoder;
+use ruzstd::verify::{VerifyError, VerifyErrorKind, VerifyReport};
+use sparse::SparseWriter;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Prints messages to stderr depending on the verbosity
+struct Log {
+    verbosity: u8,
+}
+
+impl Log {
+    fn error(&self, msg: &str) {
+        if self.verbosity >= 1 {
+            eprintln!("ruzstd: {}", msg);
+        }
+    }
+
+    fn info(&self, msg: &str) {
+        if self.verbosity >= 2 {
+            eprintln!("{}", msg);
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_default();
+    let args: Vec<String> = args.collect();
+    std::process::exit(run(&program, &args));
+}
+
+/// Returns the exit code
+fn run(program: &str, args: &[String]) -> i32 {
+    let options = match args::parse_command_line(program, args) {
+        Ok(options) => options,
+        Err(m) => {
+            eprintln!("ruzstd: {}", m);
+            eprint!("{}", args::USAGE);
+            return 1;
+        }
+    };
+    let log = Log {
+        verbosity: options.verbosity,
+    };
+
+    match options.mode {
+        Mode::Help => {
+            print!("{}", args::USAGE);
+            0
+        }
+        Mode::Version => {
+            println!("ruzstd {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        Mode::Compress => {
+            log.error("compression is not supported, use -d to decompress");
+            1
+        }
+        Mode::Train => {
+            log.error("dictionary training is not supported yet");
+            1
+        }
+        Mode::Decompress => decompress_files(&options, &log),
+        Mode::List => list_files(&options, &log),
+        Mode::Test => test_files(&options, &log),
+        Mode::Bench => bench_files(&options, &log),
+    }
+}
+
+fn list_files(options: &Options, log: &Log) -> i32 {
+    if options.files.is_empty() {
+        log.error("--list does not support reading from standard input");
+        return 1;
+    }
+
+    let (files, walked) = files::input_files(options, log);
+    let mut exit_code = if walked { 0 } else { 1 };
+    let mut infos = Vec::new();
+    for file in &files {
+        match list::FileInfo::read(file) {
+            Ok(info) => infos.push((file.clone(), info)),
+            Err(m) => {
+                log.error(&format!("{}: {}", display_name(file), m));
+                exit_code = 1;
+            }
+        }
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let result = if options.verbosity >= 3 {
+        infos
+            .iter()
+            .try_for_each(|(name, info)| list::print_verbose(&mut out, name, info))
+    } else if !infos.is_empty() {
+        list::print_summary(&mut out, &infos)
+    } else {
+        Ok(())
+    };
+    match result {
+        Ok(()) => exit_code,
+        // e.g. piped into head, nothing left to tell anyone
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => exit_code,
+        Err(e) => {
+            log.error(&e.to_string());
+            1
+        }
+    }
+}
+
+/// Where the decompressed data of all inputs goes
+enum Destination {
+    /// Next to each input file, with the suffix removed
+    PerFile,
+    Stdout,
+    /// All inputs are decompressed into the same file
+    File(PathBuf),
+}
+
+fn decompress_files(options: &Options, log: &Log) -> i32 {
+    let settings = match DecoderSettings::load(options) {
+        Ok(settings) => settings,
+        Err(m) => {
+            log.error(&m);
+            return 1;
+        }
+    };
+
+    let (mut files, walked) = files::input_files(options, log);
+    if options.files.is_empty() {
+        files.push("-".to_owned());
+    }
+    let show_progress = log.verbosity >= 2 && std::io::stderr().is_terminal();
+
+    let destination = if options.stdout {
+        Destination::Stdout
+    } else if let Some(output) = &options.output {
+        Destination::File(output.clone())
+    } else if files.iter().all(|f| f == "-") {
+        Destination::Stdout
+    } else {
+        Destination::PerFile
+    };
+
+    // a second handle to the -o file, to give it the metadata of the input at the end
+    let mut output_file = None;
+    let mut shared_output: Box<dyn Write> = match &destination {
+        Destination::Stdout => {
+            let stdout = std::io::stdout();
+            if stdout.is_terminal() && !options.force {
+                log.error("stdout is a console, aborting");
+                return 1;
+            }
+            Box::new(BufWriter::new(stdout.lock()))
+        }
+        Destination::File(path) => match create_output(path, options.force) {
+            Ok(f) => {
+                output_file = f.try_clone().ok();
+                Box::new(BufWriter::new(output_writer(f, options)))
+            }
+            Err(m) => {
+                log.error(&format!("{}: {}", path.display(), m));
+                return 1;
+            }
+        },
+        Destination::PerFile => {
+            let ok = decompress_to_files(&files, &settings, options, log, show_progress);
+            return if ok && walked { 0 } else { 1 };
+        }
+    };
+
+    // the output of all files goes into the same place in order, so this can not be parallelized
+    let mut frame_dec = settings.frame_decoder();
+    let mut exit_code = if walked { 0 } else { 1 };
+    let mut decoded = Vec::new();
+    for file in &files {
+        let result = with_progress(&mut frame_dec, file, show_progress, |frame_dec| {
+            decompress_into(file, &mut shared_output, frame_dec, options)
+        });
+        let to_stdout = matches!(destination, Destination::Stdout);
+        if report_file(file, result, to_stdout, log) {
+            decoded.push(file);
+        } else {
+            exit_code = 1;
+        }
+    }
+    let mut written = match shared_output.flush() {
+        Ok(()) => true,
+        Err(e) => {
+            log.error(&format!("write error: {}", e));
+            false
+        }
+    };
+    if let Some(output_file) = &output_file {
+        // like zstd the metadata is only copied if the output comes from a single regular file
+        if let [file] = files.as_slice() {
+            match std::fs::metadata(file) {
+                Ok(metadata) if written && exit_code == 0 && metadata.is_file() => {
+                    files::copy_metadata(&metadata, output_file)
+                }
+                _ => {}
+            }
+        }
+        // devices like /dev/null can not be synced
+        let regular = output_file.metadata().map(|m| m.is_file()).unwrap_or(false);
+        if written && regular {
+            if let Err(e) = output_file.sync_all() {
+                log.error(&format!("write error: {}", e));
+                written = false;
+            }
+        }
+    }
+    if !written {
+        return 1;
+    }
+    // the output of the inputs may have been buffered until now, so they are only removed once it is written
+    for file in decoded {
+        if !remove_input(file, options, log) {
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+/// Decompresses each file into its own output file, on options.threads threads. Returns false if any file failed
+fn decompress_to_files(
+    files: &[String],
+    settings: &DecoderSettings,
+    options: &Options,
+    log: &Log,
+    show_progress: bool,
+) -> bool {
+    let threads = if options.threads == 0 {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    } else {
+        options.threads
+    };
+    let threads = threads.clamp(1, files.len().max(1));
+    // progress lines of multiple threads would overwrite each other
+    let show_progress = show_progress && threads == 1;
+
+    let next_file = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let worker = || {
+        let mut frame_dec = settings.frame_decoder();
+        while let Some(file) = files.get(next_file.fetch_add(1, Ordering::Relaxed)) {
+            let result = with_progress(&mut frame_dec, file, show_progress, |frame_dec| {
+                decompress_to_file(file, frame_dec, options)
+            });
+            if !(report_file(file, result, false, log) && remove_input(file, options, log)) {
+                failed.store(true, Ordering::Relaxed);
+            }
+        }
+    };
+    if threads == 1 {
+        worker();
+    } else {
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(worker);
+            }
+        });
+    }
+    !failed.load(Ordering::Relaxed)
+}
+
+/// Runs decompress while drawing a progress line for file if show_progress is set
+fn with_progress<T>(
+    frame_dec: &mut FrameDecoder,
+    file: &str,
+    show_progress: bool,
+    decompress: impl FnOnce(&mut FrameDecoder) -> T,
+) -> T {
+    if !show_progress {
+        return decompress(frame_dec);
+    }
+    let input_size = if file == "-" {
+        None
+    } else {
+        std::fs::metadata(file).ok().map(|m| m.len())
+    };
+    let mut bar = progress::ProgressBar::new(display_name(file), input_size);
+    frame_dec.set_progress_callback(move |p| bar.update(p));
+    let result = decompress(frame_dec);
+    frame_dec.clear_progress_callback();
+    progress::clear();
+    result
+}
+
+/// Reports the result of decompressing file. Returns false if it failed
+fn report_file(file: &str, result: Result<u64, String>, to_stdout: bool, log: &Log) -> bool {
+    match result {
+        Ok(size) => {
+            if !to_stdout {
+                log.info(&format!("{:<20}: {} bytes", display_name(file), size));
+            }
+            true
+        }
+        Err(m) => {
+            log.error(&format!("{}: {}", display_name(file), m));
+            false
+        }
+    }
+}
+
+/// Removes file with --rm. Only call this once the output of file has been written completely
+fn remove_input(file: &str, options: &Options, log: &Log) -> bool {
+    if options.remove_source && file != "-" && !options.stdout {
+        if let Err(e) = std::fs::remove_file(file) {
+            log.error(&format!("{}: could not remove input: {}", file, e));
+            return false;
+        }
+    }
+    true
+}
+
+/// Decodes the files without writing the output and checks the checksums, like zstd -t
+fn test_files(options: &Options, log: &Log) -> i32 {
+    let mut frame_dec = match DecoderSettings::load(options) {
+        Ok(settings) => settings.frame_decoder(),
+        Err(m) => {
+            log.error(&m);
+            return 1;
+        }
+    };
+    let (mut files, walked) = files::input_files(options, log);
+    if options.files.is_empty() {
+        files.push("-".to_owned());
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let mut exit_code = if walked { 0 } else { 1 };
+    for file in &files {
+        let report = match open_input(file, options.force) {
+            Ok(mut input) => ruzstd::verify::verify_report(&mut input, &mut frame_dec),
+            Err(message) => VerifyReport {
+                error: Some(VerifyError {
+                    kind: VerifyErrorKind::Io,
+                    offset: 0,
+                    frame_index: None,
+                    message,
+                }),
+                ..VerifyReport::default()
+            },
+        };
+        if !report.is_ok() {
+            exit_code = 1;
+        }
+
+        match options.format {
+            Format::Text => match report::failure_message(&report) {
+                None => log.info(&format!(
+                    "{:<20}: {} bytes",
+                    display_name(file),
+                    report.decompressed_size()
+                )),
+                Some(m) => log.error(&format!("{}: {}", display_name(file), m)),
+            },
+            Format::Json => {
+                if let Err(e) = report::print_json(&mut out, file, &report) {
+                    if e.kind() != std::io::ErrorKind::BrokenPipe {
+                        log.error(&e.to_string());
+                    }
+                    return 1;
+                }
+            }
+        }
+    }
+    exit_code
+}
+
+fn bench_files(options: &Options, log: &Log) -> i32 {
+    let mut frame_dec = match DecoderSettings::load(options) {
+        Ok(settings) => settings.frame_decoder(),
+        Err(m) => {
+            log.error(&m);
+            return 1;
+        }
+    };
+    let (mut files, walked) = files::input_files(options, log);
+    if options.files.is_empty() {
+        files.push("-".to_owned());
+    }
+
+    let min_time = std::time::Duration::from_secs(options.bench_seconds);
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let mut exit_code = if walked { 0 } else { 1 };
+    for file in &files {
+        let mut data = Vec::new();
+        let result = open_input(file, options.force)
+            .and_then(|mut input| {
+                input
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("read error: {}", e))
+            })
+            .and_then(|_| {
+                bench::run(
+                    &data,
+                    &mut frame_dec,
+                    min_time,
+                    options.verbosity >= 3,
+                    options.streaming,
+                )
+            });
+        match result {
+            Ok(result) => {
+                if let Err(e) = bench::print_result(&mut out, display_name(file), &result) {
+                    if e.kind() != std::io::ErrorKind::BrokenPipe {
+                        log.error(&e.to_string());
+                    }
+                    return 1;
+                }
+            }
+            Err(m) => {
+                log.error(&format!("{}: {}", display_name(file), m));
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}
+
+fn display_name(file: &str) -> &str {
+    if file == "-" {
+        "*stdin*"
+    } else {
+        file
+    }
+}
+
+fn open_input(file: &str, force: bool) -> Result<Box<dyn Read>, String> {
+    if file == "-" {
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() && !force {
+            return Err("stdin is a console, aborting".to_owned());
+        }
+        return Ok(Box::new(BufReader::new(stdin.lock())));
+    }
+    if Path::new(file).is_dir() {
+        return Err("is a directory -- ignored".to_owned());
+    }
+    let f = File::open(file).map_err(|e| e.to_string())?;
+    Ok(Box::new(BufReader::new(f)))
+}
+
+fn create_output(path: &Path, force: bool) -> Result<File, String> {
+    if path.exists() && !force {
+        return Err("already exists; not overwritten".to_owned());
+    }
+    File::create(path).map_err(|e| e.to_string())
+}
+
+/// Decompresses file into the shared output. Returns the decompressed size
+fn decompress_into(
+    file: &str,
+    output: &mut Box<dyn Write>,
+    frame_dec: &mut FrameDecoder,
+    options: &Options,
+) -> Result<u64, String> {
+    let mut input = open_input(file, options.force)?;
+    decompress::decode_stream(&mut input, output, frame_dec, options.streaming)
+}
+
+/// Decompresses file into a file next to it with the suffix removed, or into the same place below the
+/// --output-dir-mirror directory. Returns the decompressed size
+fn decompress_to_file(
+    file: &str,
+    frame_dec: &mut FrameDecoder,
+    options: &Options,
+) -> Result<u64, String> {
+    let mut output_path =
+        decompress::output_name(file).ok_or_else(|| "unknown suffix -- ignored".to_owned())?;
+    let mut input = open_input(file, options.force)?;
+    if let Some(dir) = &options.output_dir_mirror {
+        output_path = files::mirror_path(dir, &output_path);
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+    }
+    let output = create_output(&output_path, options.force)
+        .map_err(|m| format!("{}: {}", output_path.display(), m))?;
+    let mut output = BufWriter::new(output_writer(output, options));
+
+    let result = decompress::decode_stream(&mut input, &mut output, frame_dec, options.streaming)
+        .and_then(|size| {
+            output
+                .into_inner()
+                .map_err(|e| e.into_error())
+                .and_then(|writer| writer.into_inner())
+                .map(|output_file| (output_file, size))
+                .map_err(|e| format!("write error: {}", e))
+        });
+    match result {
+        Ok((output_file, size)) => {
+            if let Ok(metadata) = std::fs::metadata(file) {
+                files::copy_metadata(&metadata, &output_file);
+            }
+            Ok(size)
+        }
+        Err(m) => {
+            // dont leave a half written file behind
+            let _ = std::fs::remove_file(&output_path);
+            Err(m)
+        }
+    }
+}
+
+/// Writes sparse files if that is not disabled and the output is a regular file
+fn output_writer(file: File, options: &Options) -> SparseWriter {
+    let regular = file.metadata().map(|m| m.is_file()).unwrap_or(false);
+    SparseWriter::new(file, options.sparse && regular)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_inputs_with_output_file() {
+        let dir = std::env::temp_dir().join(format!("ruzstd-rm-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut inputs = Vec::new();
+        let mut expected = Vec::new();
+        for name in ["z000045", "z000046"] {
+            let input = dir.join(format!("{}.zst", name));
+            std::fs::copy(format!("./decodecorpus_files/{}.zst", name), &input).unwrap();
+            inputs.push(input.to_str().unwrap().to_owned());
+            expected.extend(std::fs::read(format!("./decodecorpus_files/{}", name)).unwrap());
+        }
+        let args = |output: &str| {
+            let mut args = vec!["-d", "-q", "-f", "--rm", "-o", output];
+            args.extend(inputs.iter().map(|s| s.as_str()));
+            args.into_iter().map(|s| s.to_owned()).collect::<Vec<_>>()
+        };
+
+        // the outputs are small enough to stay in the BufWriter until the end, where writing them fails. The
+        // inputs must still be there after that
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(run("ruzstd", &args("/dev/full")), 1);
+            assert!(inputs.iter().all(|i| Path::new(i).exists()));
+        }
+
+        let output = dir.join("out");
+        assert_eq!(run("ruzstd", &args(output.to_str().unwrap())), 0);
+        assert!(inputs.iter().all(|i| !Path::new(i).exists()));
+        let written = std::fs::read(&output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(written == expected);
+    }
+}
@@ -0,0 +1,102 @@
+use ruzstd::frame_decoder::Progress;
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+const LINE_WIDTH: usize = 79;
+
+/// Draws a progress line to stderr from the progress callbacks of the FrameDecoder.
+///
+/// The callbacks only know about the current frame, so the sizes of finished frames are summed up here. The fraction
+/// done is based on the compressed bytes read if the input size is known, else on the content size of the frame.
+pub struct ProgressBar {
+    name: String,
+    input_size: Option<u64>,
+    start: Instant,
+    last_draw: Option<Instant>,
+    finished_frames_read: u64,
+    finished_frames_decoded: u64,
+    last: Option<Progress>,
+}
+
+impl ProgressBar {
+    pub fn new(name: &str, input_size: Option<u64>) -> ProgressBar {
+        ProgressBar {
+            name: name.to_owned(),
+            input_size,
+            start: Instant::now(),
+            last_draw: None,
+            finished_frames_read: 0,
+            finished_frames_decoded: 0,
+            last: None,
+        }
+    }
+
+    pub fn update(&mut self, progress: &Progress) {
+        if let Some(last) = self.last {
+            if progress.blocks_decoded <= last.blocks_decoded {
+                // a new frame started
+                self.finished_frames_read += last.bytes_read;
+                self.finished_frames_decoded += last.bytes_decoded;
+            }
+        }
+        self.last = Some(*progress);
+
+        let now = Instant::now();
+        if matches!(self.last_draw, Some(last_draw) if now - last_draw < REDRAW_INTERVAL) {
+            return;
+        }
+        self.last_draw = Some(now);
+        self.draw(progress, now - self.start);
+    }
+
+    fn draw(&self, progress: &Progress, elapsed: Duration) {
+        let read = self.finished_frames_read + progress.bytes_read;
+        let decoded = self.finished_frames_decoded + progress.bytes_decoded;
+        let fraction = match (self.input_size, progress.content_size) {
+            (Some(size), _) if size > 0 => Some(read as f64 / size as f64),
+            (None, Some(size)) if size > 0 && self.finished_frames_decoded == 0 => {
+                Some(progress.bytes_decoded as f64 / size as f64)
+            }
+            _ => None,
+        };
+
+        let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+        let speed = mib(decoded) / elapsed.as_secs_f64().max(0.001);
+        let line = match fraction.filter(|f| *f > 0.0) {
+            Some(fraction) => {
+                let fraction = fraction.min(1.0);
+                let eta = elapsed.as_secs_f64() * (1.0 - fraction) / fraction;
+                format!(
+                    "{}: {:>3.0}% {:.1} MiB, {:.1} MiB/s, ETA {}",
+                    self.name,
+                    fraction * 100.0,
+                    mib(decoded),
+                    speed,
+                    format_eta(eta)
+                )
+            }
+            None => format!("{}: {:.1} MiB, {:.1} MiB/s", self.name, mib(decoded), speed),
+        };
+        // pad with spaces so nothing of a longer previous line remains
+        eprint!("\r{:<width$.width$}", line, width = LINE_WIDTH);
+    }
+}
+
+fn format_eta(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Removes the progress line so the next message starts on an empty line
+pub fn clear() {
+    eprint!("\r{:width$}\r", "", width = LINE_WIDTH);
+}
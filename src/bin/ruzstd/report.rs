@@ -0,0 +1,179 @@
+use ruzstd::verify::{ChecksumStatus, FrameReport, VerifyError, VerifyErrorKind, VerifyReport};
+use std::io::Write;
+
+/// What -t says about a file as a whole
+pub fn status(report: &VerifyReport) -> &'static str {
+    if report.error.is_some() {
+        "error"
+    } else if report.is_ok() {
+        "ok"
+    } else {
+        "checksum_mismatch"
+    }
+}
+
+/// The message for a failed file in the text format, None if the file is ok
+pub fn failure_message(report: &VerifyReport) -> Option<String> {
+    if let Some(error) = &report.error {
+        return Some(match error.frame_index {
+            Some(idx) => format!(
+                "frame {} at offset {}: {}",
+                idx, error.offset, error.message
+            ),
+            None => error.message.clone(),
+        });
+    }
+    report
+        .frames
+        .iter()
+        .enumerate()
+        .find_map(|(idx, frame)| match frame.checksum {
+            ChecksumStatus::Mismatch {
+                from_data,
+                calculated,
+            } => Some(format!(
+                "checksum mismatch in frame {} at offset {}: expected {:08x}, calculated {:08x}",
+                idx, frame.offset, from_data, calculated
+            )),
+            _ => None,
+        })
+}
+
+/// Writes the report for one file as a single line of JSON
+pub fn print_json(out: &mut dyn Write, name: &str, report: &VerifyReport) -> std::io::Result<()> {
+    write!(
+        out,
+        "{{\"file\":{},\"status\":\"{}\",\"decompressed_size\":{},\"skippable_frames\":{},\"frames\":[",
+        json_string(name),
+        status(report),
+        report.decompressed_size(),
+        report.skippable_frames
+    )?;
+    for (idx, frame) in report.frames.iter().enumerate() {
+        if idx > 0 {
+            write!(out, ",")?;
+        }
+        print_frame(out, frame)?;
+    }
+    write!(out, "],\"error\":")?;
+    match &report.error {
+        Some(error) => print_error(out, error)?,
+        None => write!(out, "null")?,
+    }
+    writeln!(out, "}}")
+}
+
+fn print_frame(out: &mut dyn Write, frame: &FrameReport) -> std::io::Result<()> {
+    let (checksum, expected, calculated) = match frame.checksum {
+        ChecksumStatus::NotPresent => ("none", None, None),
+        ChecksumStatus::Valid => ("valid", None, None),
+        ChecksumStatus::Mismatch {
+            from_data,
+            calculated,
+        } => ("mismatch", Some(from_data), Some(calculated)),
+    };
+    write!(
+        out,
+        "{{\"offset\":{},\"compressed_size\":{},\"decompressed_size\":{},\"checksum\":\"{}\",\"checksum_expected\":{},\"checksum_calculated\":{}}}",
+        frame.offset,
+        frame.compressed_size,
+        frame.decompressed_size,
+        checksum,
+        json_checksum(expected),
+        json_checksum(calculated)
+    )
+}
+
+fn print_error(out: &mut dyn Write, error: &VerifyError) -> std::io::Result<()> {
+    let kind = match error.kind {
+        VerifyErrorKind::Io => "io",
+        VerifyErrorKind::Truncated => "truncated",
+        VerifyErrorKind::InvalidFrame => "invalid_frame",
+        VerifyErrorKind::DictNotProvided { .. } => "dict_not_provided",
+    };
+    write!(
+        out,
+        "{{\"kind\":\"{}\",\"offset\":{},\"frame\":",
+        kind, error.offset
+    )?;
+    match error.frame_index {
+        Some(idx) => write!(out, "{}", idx)?,
+        None => write!(out, "null")?,
+    }
+    if let VerifyErrorKind::DictNotProvided { dict_id } = error.kind {
+        write!(out, ",\"dict_id\":{}", dict_id)?;
+    }
+    write!(out, ",\"message\":{}}}", json_string(&error.message))
+}
+
+/// Checksums are written as hex strings like zstd -lv shows them
+fn json_checksum(checksum: Option<u32>) -> String {
+    match checksum {
+        Some(checksum) => format!("\"{:08x}\"", checksum),
+        None => "null".to_owned(),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        assert_eq!(json_string("a.zst"), "\"a.zst\"");
+        assert_eq!(
+            json_string("a \"b\"\\c\n\u{1}"),
+            "\"a \\\"b\\\"\\\\c\\n\\u0001\""
+        );
+    }
+
+    #[test]
+    fn json_report() {
+        let report = VerifyReport {
+            frames: vec![FrameReport {
+                offset: 0,
+                compressed_size: 20,
+                decompressed_size: 100,
+                has_checksum: true,
+                checksum: ChecksumStatus::Mismatch {
+                    from_data: 1,
+                    calculated: 0xabcdef12,
+                },
+            }],
+            skippable_frames: 1,
+            error: Some(VerifyError {
+                kind: VerifyErrorKind::DictNotProvided { dict_id: 7 },
+                offset: 28,
+                frame_index: Some(1),
+                message: "no dict".to_owned(),
+            }),
+        };
+        let mut out = Vec::new();
+        print_json(&mut out, "x.zst", &report).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"file\":\"x.zst\",\"status\":\"error\",\"decompressed_size\":100,\"skippable_frames\":1,\
+             \"frames\":[{\"offset\":0,\"compressed_size\":20,\"decompressed_size\":100,\
+             \"checksum\":\"mismatch\",\"checksum_expected\":\"00000001\",\"checksum_calculated\":\"abcdef12\"}],\
+             \"error\":{\"kind\":\"dict_not_provided\",\"offset\":28,\"frame\":1,\"dict_id\":7,\"message\":\"no dict\"}}\n"
+        );
+    }
+}
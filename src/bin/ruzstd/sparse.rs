@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+
+/// Holes are only made for zero segments that are aligned to this size, like zstd does
+const SEGMENT_SIZE: u64 = 32 * 1024;
+static ZEROS: [u8; SEGMENT_SIZE as usize] = [0; SEGMENT_SIZE as usize];
+
+/// Writes into a file but skips over segments that only contain zeros instead of writing them, so the filesystem can
+/// leave holes there. Long zero runs typically come from RLE blocks, e.g. in decompressed disk images.
+///
+/// flush() makes sure the file has its full length even if it ends with a hole.
+pub struct SparseWriter {
+    file: File,
+    sparse: bool,
+    /// Where the next byte goes
+    position: u64,
+    /// Where the file cursor is, behind position if zeros have been skipped
+    file_position: u64,
+}
+
+impl SparseWriter {
+    /// With sparse = false everything is written as is
+    pub fn new(file: File, sparse: bool) -> SparseWriter {
+        SparseWriter {
+            file,
+            sparse,
+            position: 0,
+            file_position: 0,
+        }
+    }
+
+    /// Flushes and returns the file
+    pub fn into_inner(mut self) -> std::io::Result<File> {
+        self.flush()?;
+        Ok(self.file)
+    }
+}
+
+impl Write for SparseWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !self.sparse {
+            let written = self.file.write(buf)?;
+            self.position += written as u64;
+            self.file_position = self.position;
+            return Ok(written);
+        }
+
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let in_segment = self.position % SEGMENT_SIZE;
+            let len = usize::min(rest.len(), (SEGMENT_SIZE - in_segment) as usize);
+            let (piece, tail) = rest.split_at(len);
+            let segment_start = self.position - in_segment;
+            // nothing of the segment has been written yet, so everything in it up to position was zeros. The zeros
+            // can come in over several writes, e.g. from a BufWriter with a smaller buffer than a segment
+            let skipped_so_far = self.file_position <= segment_start;
+            if !(skipped_so_far && piece.iter().all(|&b| b == 0)) {
+                if skipped_so_far {
+                    // the segment is not all zeros after all, the zeros at its start have to be written too
+                    if self.file_position != segment_start {
+                        self.file.seek(SeekFrom::Start(segment_start))?;
+                    }
+                    self.file.write_all(&ZEROS[..in_segment as usize])?;
+                }
+                self.file.write_all(piece)?;
+                self.file_position = self.position + len as u64;
+            }
+            self.position += len as u64;
+            rest = tail;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.file_position != self.position {
+            // the file ends with skipped zeros, extending it creates the last hole
+            self.file.set_len(self.position)?;
+            self.file.seek(SeekFrom::Start(self.position))?;
+            self.file_position = self.position;
+        }
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_runs() {
+        let path = std::env::temp_dir().join(format!("ruzstd-sparse-test-{}", std::process::id()));
+        let mut expected = vec![1u8; 1000];
+        expected.extend(vec![0; 3 * SEGMENT_SIZE as usize]);
+        expected.extend(vec![2; 10]);
+        expected.extend(vec![0; 2 * SEGMENT_SIZE as usize + 5]);
+
+        let mut writer = SparseWriter::new(File::create(&path).unwrap(), true);
+        for chunk in expected.chunks(7000) {
+            writer.write_all(chunk).unwrap();
+        }
+        let file = writer.into_inner().unwrap();
+        drop(file);
+
+        let written = std::fs::read(&path).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written == expected);
+        // the zero segments were skipped, so less than the whole length is allocated
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert!(metadata.blocks() * 512 < metadata.len());
+        }
+    }
+}
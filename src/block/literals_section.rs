@@ -7,6 +7,7 @@ pub struct LiteralsSection {
     pub ls_type: LiteralsSectionType,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LiteralsSectionType {
     Raw,
     RLE,
@@ -25,6 +26,12 @@ impl std::fmt::Display for LiteralsSectionType {
     }
 }
 
+impl Default for LiteralsSection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LiteralsSection {
     pub fn new() -> LiteralsSection {
         LiteralsSection {
@@ -133,7 +140,7 @@ impl LiteralsSection {
                     0 => {
                         self.num_streams = Some(1);
                     }
-                    1 | 2 | 3 => {
+                    1..=3 => {
                         self.num_streams = Some(4);
                     }
                     _ => panic!(
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod block;
 pub mod literals_section;
 pub mod sequence_section;
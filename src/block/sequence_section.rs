@@ -18,6 +18,7 @@ impl std::fmt::Display for Sequence {
 
 #[derive(Copy, Clone)]
 pub struct CompressionModes(u8);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ModeType {
     Predefined,
     RLE,
@@ -49,6 +50,12 @@ impl CompressionModes {
     }
 }
 
+impl Default for SequencesHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SequencesHeader {
     pub fn new() -> SequencesHeader {
         SequencesHeader {
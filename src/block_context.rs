@@ -0,0 +1,106 @@
+use crate::block::block::BlockType;
+use crate::decoding::block_decoder::{self, BlockDecoder};
+use crate::decoding::dictionary::Dictionary;
+use crate::decoding::scratch::DecoderScratch;
+use std::sync::Arc;
+
+/// Decodes bare zstd blocks that are not part of a frame, like ZSTD_decompressBlock in the reference implementation.
+/// The sizes of the blocks have to be known from somewhere else, e.g. the metadata of a page.
+///
+/// The context keeps the last window_size bytes of output and the entropy tables of the previous block,
+/// so blocks that reference earlier blocks have to be decoded in order with the same context.
+/// Blocks that were stored uncompressed need to be passed to insert_block to keep the history complete.
+///
+/// There is no matching encoder yet as ruzstd can not compress.
+///
+/// ```no_run
+/// use ruzstd::block_context::BlockContext;
+///
+/// fn decode_pages(pages: &[&[u8]]) -> Vec<u8> {
+///     let mut ctx = BlockContext::new(1024 * 1024);
+///     let mut result = Vec::new();
+///     for page in pages {
+///         ctx.decode_block(page, &mut result).unwrap();
+///     }
+///     result
+/// }
+/// ```
+pub struct BlockContext {
+    scratch: DecoderScratch,
+    block_dec: BlockDecoder,
+    window_size: usize,
+    dict: Option<Arc<Dictionary>>,
+}
+
+impl BlockContext {
+    pub fn new(window_size: usize) -> BlockContext {
+        BlockContext {
+            scratch: DecoderScratch::new(window_size),
+            block_dec: block_decoder::new(),
+            window_size,
+            dict: None,
+        }
+    }
+
+    /// The dict provides the initial entropy tables, offset history and content the first blocks can reference
+    pub fn with_dict(window_size: usize, dict: Arc<Dictionary>) -> BlockContext {
+        let mut ctx = BlockContext::new(window_size);
+        ctx.dict = Some(dict);
+        ctx.reset();
+        ctx
+    }
+
+    /// Forget all previous blocks, as if the context was just created. This is needed after an error
+    /// because a failed block leaves the history in an undefined state.
+    pub fn reset(&mut self) {
+        self.scratch.reset(self.window_size);
+        if let Some(dict) = &self.dict {
+            self.scratch.use_dict(dict);
+        }
+    }
+
+    /// Decodes the content of one compressed block (without the 3 byte block header) and appends the output to target.
+    /// Returns how many bytes were appended.
+    pub fn decode_block(&mut self, block: &[u8], target: &mut Vec<u8>) -> Result<usize, String> {
+        // the size field of the header only has 21 bits, bigger blocks would wrap around
+        if block.len() > block_decoder::ABSOLUTE_MAXIMUM_BLOCK_SIZE as usize {
+            return Err(format!(
+                "Blocksize was bigger than the absolute maximum 128kb. Is: {}",
+                block.len()
+            ));
+        }
+        // go through read_block_header so the block gets the same checks as in a frame
+        let raw_header = ((block.len() as u32) << 3) | (2 << 1);
+        let (header, _) = self
+            .block_dec
+            .read_block_header(&mut &raw_header.to_le_bytes()[..3])?;
+        debug_assert!(header.block_type == BlockType::Compressed);
+
+        let old_len = self.scratch.buffer.len();
+        let mut source = block;
+        self.block_dec
+            .decode_block_content(&header, &mut self.scratch, &mut source)?;
+
+        let decoded = &self.scratch.buffer.buffer[old_len..];
+        target.extend_from_slice(decoded);
+        let amount = decoded.len();
+        self.trim_history();
+        Ok(amount)
+    }
+
+    /// Adds data to the history as if it had been decoded from a block. Use this for blocks that were stored uncompressed.
+    pub fn insert_block(&mut self, data: &[u8]) {
+        self.scratch.buffer.push(data);
+        self.trim_history();
+    }
+
+    /// Only the last window_size bytes can be referenced by the next blocks. The buffer is allowed to grow to
+    /// twice the window before it is trimmed, so the history is not moved after every block.
+    fn trim_history(&mut self) {
+        if self.scratch.buffer.len() > 2 * self.window_size {
+            if let Some(amount) = self.scratch.buffer.can_drain_to_window_size() {
+                self.scratch.buffer.buffer.drain(..amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::decoding::dictionary::{Dictionary, DictionaryProvider};
+use crate::frame_decoder::FrameDecoder;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// A thread safe pool of FrameDecoders for decoding many small frames.
+///
+/// Decoders handed out by the pool keep their buffers and tables between uses, so after warming up decoding
+/// a frame does (mostly) not allocate anymore. All decoders of the pool know the dicts added with add_shared_dict,
+/// the dicts themselves are shared and not copied for each decoder.
+///
+/// ```
+/// use ruzstd::decoder_pool::DecoderPool;
+/// use ruzstd::frame_decoder::BlockDecodingStrategy;
+///
+/// fn decode_message(pool: &DecoderPool, mut msg: &[u8]) -> Vec<u8> {
+///     let mut frame_dec = pool.get();
+///     frame_dec.reset(&mut msg).unwrap();
+///     frame_dec.decode_blocks(&mut msg, BlockDecodingStrategy::All).unwrap();
+///     frame_dec.collect().unwrap()
+/// }
+/// ```
+pub struct DecoderPool {
+    idle: Mutex<Vec<FrameDecoder>>,
+    dicts: Vec<Arc<Dictionary>>,
+    dict_provider: Option<Arc<dyn DictionaryProvider>>,
+    max_idle: usize,
+    fuse_sequence_execution: bool,
+}
+
+/// A FrameDecoder borrowed from a DecoderPool. It derefs to the FrameDecoder and goes back into the pool when dropped.
+/// Settings changed on the borrowed decoder only last until then, the next borrower gets the settings of the pool.
+pub struct PooledDecoder<'pool> {
+    pool: &'pool DecoderPool,
+    decoder: Option<FrameDecoder>,
+}
+
+impl DecoderPool {
+    /// Create a new pool that keeps at most max_idle unused decoders around. Decoders returned while
+    /// the pool is full are dropped.
+    pub fn new(max_idle: usize) -> DecoderPool {
+        DecoderPool {
+            idle: Mutex::new(Vec::with_capacity(max_idle)),
+            dicts: Vec::new(),
+            dict_provider: None,
+            max_idle,
+            fuse_sequence_execution: false,
+        }
+    }
+
+    /// Make the dict available to all decoders of this pool, including those that are currently idle
+    pub fn add_shared_dict(&mut self, dict: Arc<Dictionary>) {
+        for decoder in self.idle.get_mut().unwrap().iter_mut() {
+            decoder.add_shared_dict(Arc::clone(&dict));
+        }
+        self.dicts.push(dict);
+    }
+
+    /// See FrameDecoder::set_dict_provider. Applies to all decoders of this pool
+    pub fn set_dict_provider(&mut self, provider: Arc<dyn DictionaryProvider>) {
+        for decoder in self.idle.get_mut().unwrap().iter_mut() {
+            decoder.set_dict_provider(Arc::clone(&provider));
+        }
+        self.dict_provider = Some(provider);
+    }
+
+    /// See FrameDecoder::set_fused_sequence_execution. Applies to all decoders of this pool
+    pub fn set_fused_sequence_execution(&mut self, fused: bool) {
+        for decoder in self.idle.get_mut().unwrap().iter_mut() {
+            decoder.set_fused_sequence_execution(fused);
+        }
+        self.fuse_sequence_execution = fused;
+    }
+
+    /// Take a decoder out of the pool or create a new one if there is no idle decoder
+    pub fn get(&self) -> PooledDecoder<'_> {
+        let decoder = self.idle.lock().unwrap().pop();
+        let decoder = match decoder {
+            Some(decoder) => decoder,
+            None => self.new_decoder(),
+        };
+        PooledDecoder {
+            pool: self,
+            decoder: Some(decoder),
+        }
+    }
+
+    /// How many decoders are currently waiting in the pool
+    pub fn idle_decoders(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    fn new_decoder(&self) -> FrameDecoder {
+        let mut decoder = FrameDecoder::new();
+        self.apply_settings(&mut decoder);
+        decoder
+    }
+
+    /// The dicts and settings every decoder of this pool starts with
+    fn apply_settings(&self, decoder: &mut FrameDecoder) {
+        for dict in &self.dicts {
+            decoder.add_shared_dict(Arc::clone(dict));
+        }
+        if let Some(provider) = &self.dict_provider {
+            decoder.set_dict_provider(Arc::clone(provider));
+        }
+        decoder.set_fused_sequence_execution(self.fuse_sequence_execution);
+    }
+
+    fn put_back(&self, mut decoder: FrameDecoder) {
+        if self.idle.lock().unwrap().len() >= self.max_idle {
+            return;
+        }
+        // whatever the last borrower changed (progress callback, prefix, frame format, ...) must not leak to the next
+        decoder.restore_defaults();
+        self.apply_settings(&mut decoder);
+
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.max_idle {
+            idle.push(decoder);
+        }
+    }
+}
+
+impl<'pool> Deref for PooledDecoder<'pool> {
+    type Target = FrameDecoder;
+
+    fn deref(&self) -> &FrameDecoder {
+        self.decoder.as_ref().unwrap()
+    }
+}
+
+impl<'pool> DerefMut for PooledDecoder<'pool> {
+    fn deref_mut(&mut self) -> &mut FrameDecoder {
+        self.decoder.as_mut().unwrap()
+    }
+}
+
+impl<'pool> Drop for PooledDecoder<'pool> {
+    fn drop(&mut self) {
+        // a decoder dropped during a panic might be in an inconsistent state, dont reuse it
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(decoder) = self.decoder.take() {
+            self.pool.put_back(decoder);
+        }
+    }
+}
@@ -4,7 +4,7 @@ pub struct BitReader<'s> {
 }
 
 impl<'s> BitReader<'s> {
-    pub fn new(source: &'s [u8]) -> BitReader {
+    pub fn new(source: &'s [u8]) -> BitReader<'s> {
         BitReader { idx: 0, source }
     }
 
@@ -23,6 +23,7 @@ impl<'s> BitReader<'s> {
         self.idx -= n;
     }
 
+    #[allow(clippy::manual_is_multiple_of)]
     pub fn get_bits(&mut self, n: usize) -> Result<u64, String> {
         if n > 64 {
             return Err("Cant serve this request. The reader is limited to 64bit".to_owned());
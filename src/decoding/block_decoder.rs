@@ -6,7 +6,9 @@ use super::super::block::sequence_section::SequencesHeader;
 use super::literals_section_decoder::decode_literals;
 use super::sequence_section_decoder::decode_sequences;
 use crate::decoding::scratch::DecoderScratch;
+use crate::decoding::sequence_execution::decode_and_execute_sequences;
 use crate::decoding::sequence_execution::execute_sequences;
+use crate::decoding::timings::{self, Phase};
 use std::io::Read;
 
 pub struct BlockDecoder {
@@ -28,7 +30,7 @@ pub fn new() -> BlockDecoder {
     }
 }
 
-const ABSOLUTE_MAXIMUM_BLOCK_SIZE: u32 = 128 * 1024;
+pub(crate) const ABSOLUTE_MAXIMUM_BLOCK_SIZE: u32 = 128 * 1024;
 
 impl BlockDecoder {
     pub fn decode_block_content(
@@ -43,6 +45,7 @@ impl BlockDecoder {
             DecoderState::ReadyToDecodeNextHeader => return Err("Cant decode next block body, while expecting to decode the header of the previous block. Results will be nonsense".to_string()),
         }
 
+        let start = timings::start(&workspace.timings);
         match header.block_type {
             BlockType::RLE => {
                 const BATCH_SIZE: usize = 512;
@@ -66,6 +69,12 @@ impl BlockDecoder {
                 }
                 let smaller = &mut buf[..single_read_size as usize];
                 workspace.buffer.push(smaller);
+                timings::record_block(
+                    |t| &mut t.rle,
+                    &mut workspace.timings,
+                    u64::from(header.decompressed_size),
+                    start,
+                );
 
                 Ok(1)
             }
@@ -98,6 +107,12 @@ impl BlockDecoder {
 
 
                 self.internal_state = DecoderState::ReadyToDecodeNextHeader;
+                timings::record_block(
+                    |t| &mut t.raw,
+                    &mut workspace.timings,
+                    u64::from(header.decompressed_size),
+                    start,
+                );
                 Ok(header.decompressed_size as u64)
             }
 
@@ -106,8 +121,15 @@ impl BlockDecoder {
             }
 
             BlockType::Compressed => {
+                let len_before = workspace.buffer.len();
                 self.decompress_block(header, workspace, source)?;
                 //unimplemented!("Decompression is not yet implemented...");
+                timings::record_block(
+                    |t| &mut t.compressed,
+                    &mut workspace.timings,
+                    (workspace.buffer.len() - len_before) as u64,
+                    start,
+                );
 
                 self.internal_state = DecoderState::ReadyToDecodeNextHeader;
                 Ok(header.content_size as u64)
@@ -161,12 +183,14 @@ impl BlockDecoder {
         }
 
         workspace.literals_buffer.clear(); //all literals of the previous block must have been used in the sequence execution anyways. just be defensive here
+        let start = timings::start(&workspace.timings);
         let bytes_used_in_literals_section = decode_literals(
             &section,
             &mut workspace.huf,
             raw_literals,
             &mut workspace.literals_buffer,
         )?;
+        timings::record_phase(&mut workspace.timings, Phase::LiteralsDecoding, start);
         assert!(
             section.regenerated_size == workspace.literals_buffer.len() as u32,
             "Wrong number of literals: {}, Should have been: {}",
@@ -202,19 +226,40 @@ impl BlockDecoder {
             println!("Slice for sequences: {}", raw.len());
         }
 
-        if seq_section.num_sequences != 0 {
+        if seq_section.num_sequences != 0 && workspace.fuse_sequence_execution {
+            if crate::VERBOSE {
+                println!("Decoding and executing sequences");
+            }
+            let start = timings::start(&workspace.timings);
+            decode_and_execute_sequences(
+                &seq_section,
+                raw,
+                &mut workspace.fse,
+                &workspace.literals_buffer,
+                &mut workspace.buffer,
+                &mut workspace.offset_hist,
+            )?;
+            timings::record_phase(&mut workspace.timings, Phase::SequenceExecution, start);
+            workspace.sequences.clear();
+        } else if seq_section.num_sequences != 0 {
+            let start = timings::start(&workspace.timings);
             decode_sequences(
                 &seq_section,
                 raw,
                 &mut workspace.fse,
                 &mut workspace.sequences,
             )?;
+            timings::record_phase(&mut workspace.timings, Phase::SequenceDecoding, start);
             if crate::VERBOSE {
                 println!("Executing sequences");
             }
+            let start = timings::start(&workspace.timings);
             execute_sequences(workspace)?;
+            timings::record_phase(&mut workspace.timings, Phase::SequenceExecution, start);
         } else {
+            let start = timings::start(&workspace.timings);
             workspace.buffer.push(&workspace.literals_buffer);
+            timings::record_phase(&mut workspace.timings, Phase::SequenceExecution, start);
             workspace.sequences.clear();
         }
 
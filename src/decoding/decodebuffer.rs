@@ -1,21 +1,24 @@
 use std::hash::Hasher;
+use std::sync::Arc;
 use twox_hash::XxHash64;
 
+use super::simd_copy::{self, CopyStrategy};
+
 pub struct Decodebuffer {
     pub buffer: Vec<u8>,
     pub dict_content: Vec<u8>,
+    /// Referenced instead of dict_content if set. A prefix is shared by all frames, so it is not copied for each one
+    pub prefix: Option<Arc<[u8]>>,
 
     pub window_size: usize,
     total_output_counter: u64,
     pub hash: XxHash64,
+    copy_strategy: CopyStrategy,
 }
 
 impl std::io::Read for Decodebuffer {
     fn read(&mut self, target: &mut [u8]) -> std::result::Result<usize, std::io::Error> {
-        let max_amount = match self.can_drain_to_window_size() {
-            Some(x) => x,
-            None => 0,
-        };
+        let max_amount = self.can_drain_to_window_size().unwrap_or_default();
 
         let amount = if max_amount > target.len() {
             target.len()
@@ -41,17 +44,30 @@ impl Decodebuffer {
         Decodebuffer {
             buffer: Vec::new(),
             dict_content: Vec::new(),
+            prefix: None,
             window_size,
             total_output_counter: 0,
             hash: XxHash64::with_seed(0),
+            copy_strategy: CopyStrategy::detect(),
         }
     }
 
+    /// Choose how matches and literals are copied into the buffer. Strategies the cpu does not support are replaced by the
+    /// best supported one. The default is the best supported strategy. This is not touched by reset()
+    pub fn set_copy_strategy(&mut self, strategy: CopyStrategy) {
+        self.copy_strategy = strategy.or_supported();
+    }
+
+    pub fn copy_strategy(&self) -> CopyStrategy {
+        self.copy_strategy
+    }
+
     pub fn reset(&mut self, window_size: usize) {
         self.window_size = window_size;
         self.buffer.clear();
         self.buffer.reserve(self.window_size);
         self.dict_content.clear();
+        self.prefix = None;
         self.total_output_counter = 0;
         self.hash = XxHash64::with_seed(0);
     }
@@ -65,36 +81,56 @@ impl Decodebuffer {
     }
 
     pub fn push(&mut self, data: &[u8]) {
-        self.buffer.extend_from_slice(data);
+        if self.copy_strategy == CopyStrategy::Scalar {
+            self.buffer.extend_from_slice(data);
+        } else {
+            self.buffer.reserve(data.len());
+            let old_len = self.buffer.len();
+            // the strategy has been checked to be supported in set_copy_strategy/new
+            unsafe {
+                simd_copy::copy_literals(
+                    self.copy_strategy,
+                    data.as_ptr(),
+                    self.buffer.as_mut_ptr().add(old_len),
+                    data.len(),
+                );
+                self.buffer.set_len(old_len + data.len());
+            }
+        }
         self.total_output_counter += data.len() as u64;
     }
 
+    /// offset must not be 0, execute_sequence rejects that before calling this
     pub fn repeat(&mut self, offset: usize, match_length: usize) -> Result<(), String> {
         if offset > self.buffer.len() {
             if self.total_output_counter <= self.window_size as u64 {
                 // at least part of that repeat is from the dictionary content
                 let bytes_from_dict = offset - self.buffer.len();
+                let dict_content: &[u8] = match &self.prefix {
+                    Some(prefix) => prefix,
+                    None => &self.dict_content,
+                };
 
-                if bytes_from_dict > self.dict_content.len() {
+                if bytes_from_dict > dict_content.len() {
                     return Err(format!(
                         "Need {} bytes from the dictionary but it is only {} bytes long",
                         bytes_from_dict,
-                        self.dict_content.len()
+                        dict_content.len()
                     ));
                 }
 
                 if bytes_from_dict < match_length {
-                    let dict_slice =
-                        &self.dict_content[self.dict_content.len() - bytes_from_dict..];
+                    let dict_slice = &dict_content[dict_content.len() - bytes_from_dict..];
                     self.buffer.extend(dict_slice);
 
                     self.total_output_counter += bytes_from_dict as u64;
                     return self.repeat(self.buffer.len(), match_length - bytes_from_dict);
                 } else {
-                    let low = self.dict_content.len() - bytes_from_dict;
+                    let low = dict_content.len() - bytes_from_dict;
                     let high = low + match_length;
-                    let dict_slice = &self.dict_content[low..high];
+                    let dict_slice = &dict_content[low..high];
                     self.buffer.extend(dict_slice);
+                    self.total_output_counter += match_length as u64;
                 }
             } else {
                 return Err(format!(
@@ -105,50 +141,35 @@ impl Decodebuffer {
             }
         } else {
             let start_idx = self.buffer.len() - offset;
-            self.buffer.reserve(match_length);
-
-            if start_idx + match_length > self.buffer.len() {
-                //need to copy byte by byte. can be optimized more but for now lets leave it like this
-                //TODO batch whats possible
-                for x in 0..match_length {
-                    self.buffer.push(self.buffer[start_idx + x]);
-                }
-            } else {
-                /*
-                const BATCH_SIZE: usize = 32;
-                let full_copies = match_length / BATCH_SIZE;
-                let partial_copy_size = match_length % BATCH_SIZE;
-
-                let mut buf = [0u8; BATCH_SIZE];
-                for x in 0..full_copies {
-                    let idx = start_idx + x * BATCH_SIZE;
-                    let source = &self.buffer.as_slice()[idx..idx + BATCH_SIZE];
-                    buf[0..BATCH_SIZE].copy_from_slice(source);
-                    self.buffer.extend(&buf[0..BATCH_SIZE]);
-                }
-
-                let idx = start_idx + full_copies * BATCH_SIZE;
-                let source = &self.buffer.as_slice()[idx..idx + partial_copy_size];
-                buf[0..partial_copy_size].copy_from_slice(source);
-                self.buffer.extend(&buf[0..partial_copy_size]);
-                */
-
-                // using this unsafe block instead of the above increases performance by ca 5% when decoding the enwik9 dataset
-                self.buffer.reserve(match_length);
-                unsafe {
-                    self.buffer.set_len(self.buffer.len() + match_length);
-                    let slice = &mut self.buffer[start_idx..];
-                    let src = slice.as_mut_ptr();
-                    let dst = src.add(slice.len() - match_length);
-                    std::ptr::copy_nonoverlapping(src, dst, match_length);
-                }
-            }
+            self.copy_match(start_idx, match_length);
             self.total_output_counter += match_length as u64;
         }
 
         Ok(())
     }
 
+    /// Appends match_length bytes starting at start_idx to the end of the buffer. The source and the
+    /// newly written bytes may overlap, in which case the pattern between start_idx and the end of the buffer is repeated.
+    ///
+    /// Copies are done in chunks which may write up to simd_copy::MAX_OVERRUN bytes past the end of the match
+    /// into the spare capacity of the buffer. These bytes are not part of the buffer and get overwritten by the next push/repeat.
+    fn copy_match(&mut self, start_idx: usize, match_length: usize) {
+        let old_len = self.buffer.len();
+        debug_assert!(start_idx < old_len);
+        self.buffer.reserve(match_length + simd_copy::MAX_OVERRUN);
+
+        unsafe {
+            let base = self.buffer.as_mut_ptr();
+            simd_copy::copy_match(
+                self.copy_strategy,
+                base.add(start_idx),
+                base.add(old_len),
+                match_length,
+            );
+            self.buffer.set_len(old_len + match_length);
+        }
+    }
+
     // Check if and how many bytes can currently be drawn from the buffer
     pub fn can_drain_to_window_size(&self) -> Option<usize> {
         if self.buffer.len() > self.window_size {
@@ -184,11 +205,8 @@ impl Decodebuffer {
             None => Ok(0),
             Some(can_drain) => {
                 self.hash.write(&self.buffer[0..can_drain]);
-                let mut buf = [0u8; 1]; //TODO batch to reasonable size
-                for x in self.buffer.drain(0..can_drain) {
-                    buf[0] = x;
-                    sink.write_all(&buf[..])?;
-                }
+                sink.write_all(&self.buffer[0..can_drain])?;
+                self.buffer.drain(0..can_drain);
                 Ok(can_drain)
             }
         }
@@ -207,11 +225,7 @@ impl Decodebuffer {
         sink: &mut dyn std::io::Write,
     ) -> Result<usize, std::io::Error> {
         self.hash.write(&self.buffer);
-        let mut buf = [0u8; 1]; //TODO batch to reasonable size
-        for x in &self.buffer {
-            buf[0] = *x;
-            sink.write_all(&buf[..])?;
-        }
+        sink.write_all(&self.buffer)?;
         let len = self.buffer.len();
         self.buffer.clear();
         Ok(len)
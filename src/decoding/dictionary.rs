@@ -1,6 +1,10 @@
 use crate::decoding::scratch::FSEScratch;
 use crate::decoding::scratch::HuffmanScratch;
+use std::collections::HashMap;
+use std::sync::Arc;
 
+/// A parsed dictionary with all tables already built. It is immutable after decode_dict so it can be wrapped in an Arc
+/// and shared by many decoders, see FrameDecoder::add_shared_dict
 pub struct Dictionary {
     pub id: u32,
     pub fse: FSEScratch,
@@ -9,6 +13,30 @@ pub struct Dictionary {
     pub offset_hist: [u32; 3],
 }
 
+/// Looks up dicts by id when a frame needs one the decoder does not know, see FrameDecoder::set_dict_provider.
+/// This allows loading dicts lazily, e.g. from disk or a registry, instead of adding all of them up front.
+///
+/// Closures of the form `Fn(u32) -> Option<Arc<Dictionary>>` implement this trait.
+pub trait DictionaryProvider: Send + Sync {
+    /// Returns the dict with the id or None if it is not known
+    fn get_dict(&self, id: u32) -> Option<Arc<Dictionary>>;
+}
+
+impl<F> DictionaryProvider for F
+where
+    F: Fn(u32) -> Option<Arc<Dictionary>> + Send + Sync,
+{
+    fn get_dict(&self, id: u32) -> Option<Arc<Dictionary>> {
+        self(id)
+    }
+}
+
+impl DictionaryProvider for HashMap<u32, Arc<Dictionary>> {
+    fn get_dict(&self, id: u32) -> Option<Arc<Dictionary>> {
+        self.get(&id).cloned()
+    }
+}
+
 impl Dictionary {
     /// parses the dictionary and set the tables
     /// it returns the dict_id for checking with the frame's dict_id
@@ -39,19 +67,19 @@ impl Dictionary {
             raw_tables,
             crate::decoding::sequence_section_decoder::OF_MAX_LOG,
         )?;
-        let raw_tables = &raw_tables[of_size as usize..];
+        let raw_tables = &raw_tables[of_size..];
 
         let ml_size = new_dict.fse.match_lengths.build_decoder(
             raw_tables,
             crate::decoding::sequence_section_decoder::ML_MAX_LOG,
         )?;
-        let raw_tables = &raw_tables[ml_size as usize..];
+        let raw_tables = &raw_tables[ml_size..];
 
         let ll_size = new_dict.fse.literal_lengths.build_decoder(
             raw_tables,
             crate::decoding::sequence_section_decoder::LL_MAX_LOG,
         )?;
-        let raw_tables = &raw_tables[ll_size as usize..];
+        let raw_tables = &raw_tables[ll_size..];
 
         let offset1 = &raw_tables[0..4];
         let offset1 = crate::decoding::little_endian::read_little_endian_u32(offset1);
@@ -53,10 +53,8 @@ fn decompress_literals(
                 println!("Built huffman table using {} bytes", bytes_read);
             }
         }
-        LiteralsSectionType::Treeless => {
-            if scratch.table.max_num_bits == 0 {
-                return Err("Tried to reuse huffman table but it was never initialized".to_owned());
-            }
+        LiteralsSectionType::Treeless if scratch.table.max_num_bits == 0 => {
+            return Err("Tried to reuse huffman table but it was never initialized".to_owned());
         }
         _ => { /* nothing to do, huffman tree has been provided by previous block */ }
     }
@@ -74,7 +72,7 @@ fn decompress_literals(
         bytes_read += 6;
         let source = &source[6..];
 
-        if source.len() < jump3 as usize {
+        if source.len() < jump3 {
             return Err(format!(
                 "Need at least {} byte to decode literals. Have: {}",
                 jump3,
@@ -83,10 +81,10 @@ fn decompress_literals(
         }
 
         //decode 4 streams
-        let stream1 = &source[..jump1 as usize];
-        let stream2 = &source[jump1 as usize..jump2 as usize];
-        let stream3 = &source[jump2 as usize..jump3 as usize];
-        let stream4 = &source[jump3 as usize..];
+        let stream1 = &source[..jump1];
+        let stream2 = &source[jump1..jump2];
+        let stream3 = &source[jump2..jump3];
+        let stream4 = &source[jump3..];
 
         for stream in &[stream1, stream2, stream3, stream4] {
             let mut decoder = HuffmanDecoder::new(&scratch.table);
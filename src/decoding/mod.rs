@@ -9,3 +9,5 @@ pub mod little_endian;
 pub mod scratch;
 pub mod sequence_execution;
 pub mod sequence_section_decoder;
+pub mod simd_copy;
+pub mod timings;
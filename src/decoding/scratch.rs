@@ -1,6 +1,8 @@
 use super::super::block::sequence_section::Sequence;
 use super::decodebuffer::Decodebuffer;
+use super::sequence_section_decoder::{default_ll_table, default_ml_table, default_of_table};
 use crate::decoding::dictionary::Dictionary;
+use crate::decoding::timings::BlockTimings;
 use crate::fse::FSETable;
 use crate::huff0::HuffmanTable;
 
@@ -13,6 +15,12 @@ pub struct DecoderScratch {
     pub literals_buffer: Vec<u8>,
     pub sequences: Vec<Sequence>,
     pub block_content_buffer: Vec<u8>,
+
+    /// Execute each sequence right after decoding it instead of collecting all sequences of a block first.
+    /// This is not touched by reset() so it stays the same for all frames decoded with this scratch
+    pub fuse_sequence_execution: bool,
+    /// Some if timings are collected. This is not touched by reset() so the counters add up over multiple frames
+    pub timings: Option<BlockTimings>,
 }
 
 impl DecoderScratch {
@@ -21,20 +29,16 @@ impl DecoderScratch {
             huf: HuffmanScratch {
                 table: HuffmanTable::new(),
             },
-            fse: FSEScratch {
-                offsets: FSETable::new(),
-                of_rle: None,
-                literal_lengths: FSETable::new(),
-                ll_rle: None,
-                match_lengths: FSETable::new(),
-                ml_rle: None,
-            },
+            fse: FSEScratch::new(),
             buffer: Decodebuffer::new(window_size),
             offset_hist: [1, 4, 8],
 
             block_content_buffer: Vec::new(),
             literals_buffer: Vec::new(),
             sequences: Vec::new(),
+
+            fuse_sequence_execution: false,
+            timings: None,
         }
     }
 
@@ -52,35 +56,45 @@ impl DecoderScratch {
         self.fse.ll_rle = None;
         self.fse.ml_rle = None;
         self.fse.of_rle = None;
+        self.fse.ll_predefined = false;
+        self.fse.ml_predefined = false;
+        self.fse.of_predefined = false;
 
         self.huf.table.reset();
     }
 
     pub fn use_dict(&mut self, dict: &Dictionary) {
-        self.fse = dict.fse.clone();
-        self.huf = dict.huf.clone();
+        self.fse.clone_from(&dict.fse);
+        self.huf.clone_from(&dict.huf);
         self.offset_hist = dict.offset_hist;
-        self.buffer.dict_content = dict.dict_content.clone();
+        self.buffer.dict_content.clone_from(&dict.dict_content);
     }
 
     /// parses the dictionary and set the tables
     /// it returns the dict_id for checking with the frame's dict_id
     pub fn load_dict(&mut self, raw: &[u8]) -> Result<u32, String> {
         let dict = super::dictionary::Dictionary::decode_dict(raw)?;
-
-        self.huf = dict.huf.clone();
-        self.fse = dict.fse.clone();
-        self.offset_hist = dict.offset_hist;
-        self.buffer.dict_content = dict.dict_content.clone();
+        self.use_dict(&dict);
         Ok(dict.id)
     }
 }
 
-#[derive(Clone)]
 pub struct HuffmanScratch {
     pub table: HuffmanTable,
 }
 
+impl Clone for HuffmanScratch {
+    fn clone(&self) -> Self {
+        HuffmanScratch {
+            table: self.table.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.table.clone_from(&source.table);
+    }
+}
+
 impl HuffmanScratch {
     pub fn new() -> HuffmanScratch {
         HuffmanScratch {
@@ -95,14 +109,46 @@ impl Default for HuffmanScratch {
     }
 }
 
-#[derive(Clone)]
 pub struct FSEScratch {
     pub offsets: FSETable,
     pub of_rle: Option<u8>,
+    pub of_predefined: bool,
     pub literal_lengths: FSETable,
     pub ll_rle: Option<u8>,
+    pub ll_predefined: bool,
     pub match_lengths: FSETable,
     pub ml_rle: Option<u8>,
+    pub ml_predefined: bool,
+}
+
+// Implemented by hand so clone_from can reuse the allocations of the tables. This makes using a dictionary
+// in a decoder that has been used before mostly free of allocations
+impl Clone for FSEScratch {
+    fn clone(&self) -> Self {
+        FSEScratch {
+            offsets: self.offsets.clone(),
+            of_rle: self.of_rle,
+            of_predefined: self.of_predefined,
+            literal_lengths: self.literal_lengths.clone(),
+            ll_rle: self.ll_rle,
+            ll_predefined: self.ll_predefined,
+            match_lengths: self.match_lengths.clone(),
+            ml_rle: self.ml_rle,
+            ml_predefined: self.ml_predefined,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.offsets.clone_from(&source.offsets);
+        self.of_rle = source.of_rle;
+        self.of_predefined = source.of_predefined;
+        self.literal_lengths.clone_from(&source.literal_lengths);
+        self.ll_rle = source.ll_rle;
+        self.ll_predefined = source.ll_predefined;
+        self.match_lengths.clone_from(&source.match_lengths);
+        self.ml_rle = source.ml_rle;
+        self.ml_predefined = source.ml_predefined;
+    }
 }
 
 impl FSEScratch {
@@ -110,10 +156,41 @@ impl FSEScratch {
         FSEScratch {
             offsets: FSETable::new(),
             of_rle: None,
+            of_predefined: false,
             literal_lengths: FSETable::new(),
             ll_rle: None,
+            ll_predefined: false,
             match_lengths: FSETable::new(),
             ml_rle: None,
+            ml_predefined: false,
+        }
+    }
+
+    /// The table used for literal lengths. This is either the table in literal_lengths or
+    /// the predefined table if the last mode that set a table was ModeType::Predefined
+    pub fn ll_table(&self) -> &FSETable {
+        if self.ll_predefined {
+            default_ll_table()
+        } else {
+            &self.literal_lengths
+        }
+    }
+
+    /// Like ll_table but for match lengths
+    pub fn ml_table(&self) -> &FSETable {
+        if self.ml_predefined {
+            default_ml_table()
+        } else {
+            &self.match_lengths
+        }
+    }
+
+    /// Like ll_table but for offsets
+    pub fn of_table(&self) -> &FSETable {
+        if self.of_predefined {
+            default_of_table()
+        } else {
+            &self.offsets
         }
     }
 }
@@ -1,4 +1,9 @@
+use super::super::block::sequence_section::Sequence;
+use super::super::block::sequence_section::SequencesHeader;
+use super::decodebuffer::Decodebuffer;
 use super::scratch::DecoderScratch;
+use super::scratch::FSEScratch;
+use super::sequence_section_decoder::decode_sequences_into;
 
 pub fn execute_sequences(scratch: &mut DecoderScratch) -> Result<(), String> {
     let mut literals_copy_counter = 0;
@@ -7,48 +12,64 @@ pub fn execute_sequences(scratch: &mut DecoderScratch) -> Result<(), String> {
 
     for idx in 0..scratch.sequences.len() {
         let seq = scratch.sequences[idx];
-        if crate::VERBOSE {}
         //println!("{}: {}", idx, seq);
 
-        if seq.ll > 0 {
-            let high = literals_copy_counter + seq.ll as usize;
-            if high > scratch.literals_buffer.len() {
-                return Err(format!(
-                    "Sequence wants to copy up to byte {}. Bytes in literalsbuffer: {}",
-                    high,
-                    scratch.literals_buffer.len()
-                ));
-            }
-            let literals = &scratch.literals_buffer[literals_copy_counter..high];
-            literals_copy_counter += seq.ll as usize;
+        execute_sequence(
+            seq,
+            &scratch.literals_buffer,
+            &mut literals_copy_counter,
+            &mut scratch.buffer,
+            &mut scratch.offset_hist,
+        )?;
 
-            //for x in literals {
-            //    println!("{}", x);
-            //}
+        seq_sum += seq.ml;
+        seq_sum += seq.ll;
+    }
+    seq_sum += push_rest_literals(
+        &scratch.literals_buffer,
+        literals_copy_counter,
+        &mut scratch.buffer,
+    );
 
-            scratch.buffer.push(literals);
-        }
+    let diff = scratch.buffer.len() - old_buffer_size;
+    assert!(
+        seq_sum as usize == diff,
+        "Seq_sum: {} is different from the difference in buffersize: {}",
+        seq_sum,
+        diff
+    );
+    Ok(())
+}
 
-        let actual_offset = do_offset_history(seq.of, seq.ll, &mut scratch.offset_hist);
-        if actual_offset == 0 {
-            return Err("Illegal offset: 0 found".to_owned());
-        }
-        if seq.ml > 0 {
-            scratch
-                .buffer
-                .repeat(actual_offset as usize, seq.ml as usize)?;
-        }
+/// Decodes the sequences of a block and executes each one right after it has been decoded instead of collecting them in a Vec first.
+/// The result in the buffer is the same as calling decode_sequences and then execute_sequences.
+pub fn decode_and_execute_sequences(
+    section: &SequencesHeader,
+    source: &[u8],
+    fse: &mut FSEScratch,
+    literals: &[u8],
+    buffer: &mut Decodebuffer,
+    offset_hist: &mut [u32; 3],
+) -> Result<(), String> {
+    let mut literals_copy_counter = 0;
+    let old_buffer_size = buffer.len();
+    let mut seq_sum = 0;
 
+    decode_sequences_into(section, source, fse, |seq| {
+        execute_sequence(
+            seq,
+            literals,
+            &mut literals_copy_counter,
+            buffer,
+            offset_hist,
+        )?;
         seq_sum += seq.ml;
         seq_sum += seq.ll;
-    }
-    if literals_copy_counter < scratch.literals_buffer.len() {
-        let rest_literals = &scratch.literals_buffer[literals_copy_counter..];
-        scratch.buffer.push(rest_literals);
-        seq_sum += rest_literals.len() as u32;
-    }
+        Ok(())
+    })?;
+    seq_sum += push_rest_literals(literals, literals_copy_counter, buffer);
 
-    let diff = scratch.buffer.len() - old_buffer_size;
+    let diff = buffer.len() - old_buffer_size;
     assert!(
         seq_sum as usize == diff,
         "Seq_sum: {} is different from the difference in buffersize: {}",
@@ -58,6 +79,58 @@ pub fn execute_sequences(scratch: &mut DecoderScratch) -> Result<(), String> {
     Ok(())
 }
 
+fn execute_sequence(
+    seq: Sequence,
+    literals_buffer: &[u8],
+    literals_copy_counter: &mut usize,
+    buffer: &mut Decodebuffer,
+    offset_hist: &mut [u32; 3],
+) -> Result<(), String> {
+    if seq.ll > 0 {
+        let high = *literals_copy_counter + seq.ll as usize;
+        if high > literals_buffer.len() {
+            return Err(format!(
+                "Sequence wants to copy up to byte {}. Bytes in literalsbuffer: {}",
+                high,
+                literals_buffer.len()
+            ));
+        }
+        let literals = &literals_buffer[*literals_copy_counter..high];
+        *literals_copy_counter += seq.ll as usize;
+
+        //for x in literals {
+        //    println!("{}", x);
+        //}
+
+        buffer.push(literals);
+    }
+
+    let actual_offset = do_offset_history(seq.of, seq.ll, offset_hist);
+    if actual_offset == 0 {
+        return Err("Illegal offset: 0 found".to_owned());
+    }
+    if seq.ml > 0 {
+        buffer.repeat(actual_offset as usize, seq.ml as usize)?;
+    }
+
+    Ok(())
+}
+
+// literals that were not used by any sequence get appended after the last sequence. Returns how many were pushed
+fn push_rest_literals(
+    literals_buffer: &[u8],
+    literals_copy_counter: usize,
+    buffer: &mut Decodebuffer,
+) -> u32 {
+    if literals_copy_counter < literals_buffer.len() {
+        let rest_literals = &literals_buffer[literals_copy_counter..];
+        buffer.push(rest_literals);
+        rest_literals.len() as u32
+    } else {
+        0
+    }
+}
+
 fn do_offset_history(offset_value: u32, lit_len: u32, scratch: &mut [u32; 3]) -> u32 {
     let actual_offset = if lit_len > 0 {
         match offset_value {
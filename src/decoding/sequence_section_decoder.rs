@@ -4,6 +4,8 @@ use super::super::block::sequence_section::SequencesHeader;
 use super::bit_reader_reverse::BitReaderReversed;
 use super::scratch::FSEScratch;
 use crate::fse::FSEDecoder;
+use crate::fse::FSETable;
+use std::sync::OnceLock;
 
 pub fn decode_sequences(
     section: &SequencesHeader,
@@ -11,6 +13,26 @@ pub fn decode_sequences(
     scratch: &mut FSEScratch,
     target: &mut Vec<Sequence>,
 ) -> Result<(), String> {
+    target.clear();
+    target.reserve(section.num_sequences as usize);
+
+    decode_sequences_into(section, source, scratch, |seq| {
+        target.push(seq);
+        Ok(())
+    })
+}
+
+/// Like decode_sequences but instead of collecting the sequences into a Vec each sequence is passed to the sink as soon as it is decoded.
+/// If the sink returns an error decoding stops and the error is returned.
+pub fn decode_sequences_into<F>(
+    section: &SequencesHeader,
+    source: &[u8],
+    scratch: &mut FSEScratch,
+    sink: F,
+) -> Result<(), String>
+where
+    F: FnMut(Sequence) -> Result<(), String>,
+{
     let bytes_read = maybe_update_fse_tables(section, source, scratch)?;
 
     if crate::VERBOSE {
@@ -36,21 +58,24 @@ pub fn decode_sequences(
     }
 
     if scratch.ll_rle.is_some() || scratch.ml_rle.is_some() || scratch.of_rle.is_some() {
-        decode_sequences_with_rle(section, &mut br, scratch, target)
+        decode_sequences_with_rle(section, &mut br, scratch, sink)
     } else {
-        decode_sequences_without_rle(section, &mut br, scratch, target)
+        decode_sequences_without_rle(section, &mut br, scratch, sink)
     }
 }
 
-fn decode_sequences_with_rle(
+fn decode_sequences_with_rle<F>(
     section: &SequencesHeader,
     br: &mut BitReaderReversed,
     scratch: &mut FSEScratch,
-    target: &mut Vec<Sequence>,
-) -> Result<(), String> {
-    let mut ll_dec = FSEDecoder::new(&scratch.literal_lengths);
-    let mut ml_dec = FSEDecoder::new(&scratch.match_lengths);
-    let mut of_dec = FSEDecoder::new(&scratch.offsets);
+    mut sink: F,
+) -> Result<(), String>
+where
+    F: FnMut(Sequence) -> Result<(), String>,
+{
+    let mut ll_dec = FSEDecoder::new(scratch.ll_table());
+    let mut ml_dec = FSEDecoder::new(scratch.ml_table());
+    let mut of_dec = FSEDecoder::new(scratch.of_table());
 
     if scratch.ll_rle.is_none() {
         ll_dec.init_state(br)?;
@@ -62,25 +87,19 @@ fn decode_sequences_with_rle(
         ml_dec.init_state(br)?;
     }
 
-    target.clear();
-    target.reserve(section.num_sequences as usize);
-
-    for _seq_idx in 0..section.num_sequences {
+    for seq_idx in 0..section.num_sequences {
         //get the codes from either the RLE byte or from the decoder
-        let ll_code = if scratch.ll_rle.is_some() {
-            scratch.ll_rle.unwrap()
-        } else {
-            ll_dec.decode_symbol()
+        let ll_code = match scratch.ll_rle {
+            Some(rle) => rle,
+            None => ll_dec.decode_symbol(),
         };
-        let ml_code = if scratch.ml_rle.is_some() {
-            scratch.ml_rle.unwrap()
-        } else {
-            ml_dec.decode_symbol()
+        let ml_code = match scratch.ml_rle {
+            Some(rle) => rle,
+            None => ml_dec.decode_symbol(),
         };
-        let of_code = if scratch.of_rle.is_some() {
-            scratch.of_rle.unwrap()
-        } else {
-            of_dec.decode_symbol()
+        let of_code = match scratch.of_rle {
+            Some(rle) => rle,
+            None => of_dec.decode_symbol(),
         };
 
         let (ll_value, ll_num_bits) = lookup_ll_code(ll_code);
@@ -109,13 +128,13 @@ fn decode_sequences_with_rle(
             return Err("Read an offset == 0. That is an illegal value for offsets".to_owned());
         }
 
-        target.push(Sequence {
-            ll: ll_value as u32 + ll_add as u32,
-            ml: ml_value as u32 + ml_add as u32,
+        sink(Sequence {
+            ll: ll_value + ll_add as u32,
+            ml: ml_value + ml_add as u32,
             of: offset,
-        });
+        })?;
 
-        if target.len() < section.num_sequences as usize {
+        if seq_idx + 1 < section.num_sequences {
             //println!(
             //    "Bits left: {} ({} bytes)",
             //    br.bits_remaining(),
@@ -150,24 +169,24 @@ fn decode_sequences_with_rle(
     }
 }
 
-fn decode_sequences_without_rle(
+fn decode_sequences_without_rle<F>(
     section: &SequencesHeader,
     br: &mut BitReaderReversed,
     scratch: &mut FSEScratch,
-    target: &mut Vec<Sequence>,
-) -> Result<(), String> {
-    let mut ll_dec = FSEDecoder::new(&scratch.literal_lengths);
-    let mut ml_dec = FSEDecoder::new(&scratch.match_lengths);
-    let mut of_dec = FSEDecoder::new(&scratch.offsets);
+    mut sink: F,
+) -> Result<(), String>
+where
+    F: FnMut(Sequence) -> Result<(), String>,
+{
+    let mut ll_dec = FSEDecoder::new(scratch.ll_table());
+    let mut ml_dec = FSEDecoder::new(scratch.ml_table());
+    let mut of_dec = FSEDecoder::new(scratch.of_table());
 
     ll_dec.init_state(br)?;
     of_dec.init_state(br)?;
     ml_dec.init_state(br)?;
 
-    target.clear();
-    target.reserve(section.num_sequences as usize);
-
-    for _seq_idx in 0..section.num_sequences {
+    for seq_idx in 0..section.num_sequences {
         let ll_code = ll_dec.decode_symbol();
         let ml_code = ml_dec.decode_symbol();
         let of_code = of_dec.decode_symbol();
@@ -187,13 +206,13 @@ fn decode_sequences_without_rle(
             return Err("Read an offset == 0. That is an illegal value for offsets".to_owned());
         }
 
-        target.push(Sequence {
-            ll: ll_value as u32 + ll_add as u32,
-            ml: ml_value as u32 + ml_add as u32,
+        sink(Sequence {
+            ll: ll_value + ll_add as u32,
+            ml: ml_value + ml_add as u32,
             of: offset,
-        });
+        })?;
 
-        if target.len() < section.num_sequences as usize {
+        if seq_idx + 1 < section.num_sequences {
             //println!(
             //    "Bits left: {} ({} bytes)",
             //    br.bits_remaining(),
@@ -303,6 +322,7 @@ fn maybe_update_fse_tables(
                 println!("Updating ll table");
                 println!("Used bytes: {}", bytes);
             }
+            scratch.ll_predefined = false;
             scratch.ll_rle = None;
         }
         ModeType::RLE => {
@@ -319,10 +339,7 @@ fn maybe_update_fse_tables(
             if crate::VERBOSE {
                 println!("Use predefined ll table");
             }
-            scratch.literal_lengths.build_from_probabilities(
-                LL_DEFAULT_ACC_LOG,
-                &Vec::from(&LITERALS_LENGTH_DEFAULT_DISTRIBUTION[..]),
-            )?;
+            scratch.ll_predefined = true;
             scratch.ll_rle = None;
         }
         ModeType::Repeat => {
@@ -343,6 +360,7 @@ fn maybe_update_fse_tables(
                 println!("Used bytes: {}", bytes);
             }
             bytes_read += bytes;
+            scratch.of_predefined = false;
             scratch.of_rle = None;
         }
         ModeType::RLE => {
@@ -359,10 +377,7 @@ fn maybe_update_fse_tables(
             if crate::VERBOSE {
                 println!("Use predefined of table");
             }
-            scratch.offsets.build_from_probabilities(
-                OF_DEFAULT_ACC_LOG,
-                &Vec::from(&OFFSET_DEFAULT_DISTRIBUTION[..]),
-            )?;
+            scratch.of_predefined = true;
             scratch.of_rle = None;
         }
         ModeType::Repeat => {
@@ -383,6 +398,7 @@ fn maybe_update_fse_tables(
                 println!("Updating ml table");
                 println!("Used bytes: {}", bytes);
             }
+            scratch.ml_predefined = false;
             scratch.ml_rle = None;
         }
         ModeType::RLE => {
@@ -399,10 +415,7 @@ fn maybe_update_fse_tables(
             if crate::VERBOSE {
                 println!("Use predefined ml table");
             }
-            scratch.match_lengths.build_from_probabilities(
-                ML_DEFAULT_ACC_LOG,
-                &Vec::from(&MATCH_LENGTH_DEFAULT_DISTRIBUTION[..]),
-            )?;
+            scratch.ml_predefined = true;
             scratch.ml_rle = None;
         }
         ModeType::Repeat => {
@@ -433,6 +446,35 @@ const OFFSET_DEFAULT_DISTRIBUTION: [i32; 29] = [
     1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
 ];
 
+fn build_default_table(acc_log: u8, distribution: &[i32]) -> FSETable {
+    let mut table = FSETable::new();
+    table
+        .build_from_probabilities(acc_log, distribution)
+        .expect("the predefined distributions are valid");
+    table
+}
+
+/// The table for ModeType::Predefined literal lengths. It is built once and then shared by all decoders
+pub fn default_ll_table() -> &'static FSETable {
+    static TABLE: OnceLock<FSETable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        build_default_table(LL_DEFAULT_ACC_LOG, &LITERALS_LENGTH_DEFAULT_DISTRIBUTION)
+    })
+}
+
+/// The table for ModeType::Predefined match lengths. It is built once and then shared by all decoders
+pub fn default_ml_table() -> &'static FSETable {
+    static TABLE: OnceLock<FSETable> = OnceLock::new();
+    TABLE
+        .get_or_init(|| build_default_table(ML_DEFAULT_ACC_LOG, &MATCH_LENGTH_DEFAULT_DISTRIBUTION))
+}
+
+/// The table for ModeType::Predefined offsets. It is built once and then shared by all decoders
+pub fn default_of_table() -> &'static FSETable {
+    static TABLE: OnceLock<FSETable> = OnceLock::new();
+    TABLE.get_or_init(|| build_default_table(OF_DEFAULT_ACC_LOG, &OFFSET_DEFAULT_DISTRIBUTION))
+}
+
 #[test]
 fn test_ll_default() {
     let mut table = crate::fse::FSETable::new();
@@ -473,3 +515,24 @@ fn test_ll_default() {
     assert!(table.decode[59].num_bits == 5);
     assert!(table.decode[59].base_line == 32);
 }
+
+#[test]
+fn test_default_tables_shared() {
+    let table = default_ll_table();
+    assert!(std::ptr::eq(table, default_ll_table()));
+    assert!(table.decode.len() == 1 << LL_DEFAULT_ACC_LOG);
+    assert!(default_ml_table().decode.len() == 1 << ML_DEFAULT_ACC_LOG);
+    assert!(default_of_table().decode.len() == 1 << OF_DEFAULT_ACC_LOG);
+
+    // same values as in test_ll_default
+    assert!(table.decode[19].symbol == 27);
+    assert!(table.decode[19].num_bits == 6);
+    assert!(table.decode[39].symbol == 25);
+    assert!(table.decode[39].base_line == 16);
+
+    let mut scratch = crate::decoding::scratch::FSEScratch::new();
+    scratch.ll_predefined = true;
+    assert!(std::ptr::eq(scratch.ll_table(), table));
+    scratch.ll_predefined = false;
+    assert!(std::ptr::eq(scratch.ll_table(), &scratch.literal_lengths));
+}
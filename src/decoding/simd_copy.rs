@@ -0,0 +1,165 @@
+/// Which instructions the Decodebuffer uses to copy matches and literals.
+///
+/// Without the "simd" feature or on targets other than x86_64 all strategies behave like Scalar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CopyStrategy {
+    Scalar,
+    Sse2,
+    Avx2,
+}
+
+/// How many bytes a match copy may write past the end of the match. The Decodebuffer reserves this much additional space.
+pub const MAX_OVERRUN: usize = 32;
+
+impl CopyStrategy {
+    /// The best strategy the current cpu supports
+    pub fn detect() -> CopyStrategy {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                CopyStrategy::Avx2
+            } else {
+                // sse2 is part of the x86_64 baseline
+                CopyStrategy::Sse2
+            }
+        }
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        {
+            CopyStrategy::Scalar
+        }
+    }
+
+    /// Use self if the cpu supports it, else the best supported strategy
+    pub fn or_supported(self) -> CopyStrategy {
+        CopyStrategy::min(self, CopyStrategy::detect())
+    }
+}
+
+/// Copies match_length bytes from src to dst where dst - src is the offset of the match.
+/// Like the scalar version this may write up to MAX_OVERRUN bytes after dst + match_length.
+///
+/// # Safety
+/// src < dst, [src, dst) must be initialized, dst + match_length + MAX_OVERRUN must be writable
+/// and the strategy must be supported by the cpu (see CopyStrategy::or_supported)
+pub unsafe fn copy_match(strategy: CopyStrategy, src: *mut u8, dst: *mut u8, match_length: usize) {
+    match strategy {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        CopyStrategy::Sse2 => x86::copy_match_sse2(src, dst, match_length),
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        CopyStrategy::Avx2 => x86::copy_match_avx2(src, dst, match_length),
+        _ => copy_match_scalar(src, dst, match_length),
+    }
+}
+
+/// Copies len bytes from src to dst which must not overlap. Does not write past dst + len.
+///
+/// # Safety
+/// Same as ptr::copy_nonoverlapping and the strategy must be supported by the cpu
+pub unsafe fn copy_literals(strategy: CopyStrategy, src: *const u8, dst: *mut u8, len: usize) {
+    match strategy {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        CopyStrategy::Sse2 if (16..=32).contains(&len) => x86::copy_16_to_32(src, dst, len),
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        CopyStrategy::Avx2 if (16..=32).contains(&len) => x86::copy_16_to_32(src, dst, len),
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        CopyStrategy::Avx2 if (32..=64).contains(&len) => x86::copy_32_to_64(src, dst, len),
+        _ => std::ptr::copy_nonoverlapping(src, dst, len),
+    }
+}
+
+const WILDCOPY_CHUNK: usize = 16;
+
+unsafe fn copy_match_scalar(src: *mut u8, mut dst: *mut u8, match_length: usize) {
+    let mut remaining = match_length;
+
+    // For offsets smaller than a chunk, copying a chunk would read bytes that are not yet written.
+    // The match repeats with a period of offset, so the already produced pattern can be copied as a whole,
+    // which doubles the distance between src and dst each time until it is big enough for chunked copies
+    while (dst as usize - src as usize) < WILDCOPY_CHUNK && remaining > 0 {
+        let distance = dst as usize - src as usize;
+        let amount = usize::min(distance, remaining);
+        std::ptr::copy_nonoverlapping(src, dst, amount);
+        dst = dst.add(amount);
+        remaining -= amount;
+    }
+
+    // src and dst are now at least WILDCOPY_CHUNK bytes apart so every chunk only reads bytes that have already been written
+    let mut copied = 0;
+    while copied < remaining {
+        std::ptr::copy_nonoverlapping(src.add(copied), dst.add(copied), WILDCOPY_CHUNK);
+        copied += WILDCOPY_CHUNK;
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    // Fills a register with the offset bytes at src repeated. Writing it every (16 / offset) * offset bytes
+    // produces the match for offsets smaller than 16
+    #[inline(always)]
+    unsafe fn pattern(src: *const u8, offset: usize) -> (__m128i, usize) {
+        let mut pattern = [0u8; 16];
+        for (idx, byte) in pattern.iter_mut().enumerate() {
+            *byte = *src.add(idx % offset);
+        }
+        let step = 16 - (16 % offset);
+        (_mm_loadu_si128(pattern.as_ptr() as *const __m128i), step)
+    }
+
+    #[inline(always)]
+    unsafe fn copy_short_offset(src: *const u8, dst: *mut u8, offset: usize, match_length: usize) {
+        let (pattern, step) = pattern(src, offset);
+        let mut written = 0;
+        while written < match_length {
+            _mm_storeu_si128(dst.add(written) as *mut __m128i, pattern);
+            written += step;
+        }
+    }
+
+    pub unsafe fn copy_match_sse2(src: *mut u8, dst: *mut u8, match_length: usize) {
+        let offset = dst as usize - src as usize;
+        if offset < 16 {
+            copy_short_offset(src, dst, offset, match_length);
+            return;
+        }
+        let mut copied = 0;
+        while copied < match_length {
+            let chunk = _mm_loadu_si128(src.add(copied) as *const __m128i);
+            _mm_storeu_si128(dst.add(copied) as *mut __m128i, chunk);
+            copied += 16;
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn copy_match_avx2(src: *mut u8, dst: *mut u8, match_length: usize) {
+        let offset = dst as usize - src as usize;
+        if offset < 32 {
+            copy_match_sse2(src, dst, match_length);
+            return;
+        }
+        let mut copied = 0;
+        while copied < match_length {
+            let chunk = _mm256_loadu_si256(src.add(copied) as *const __m256i);
+            _mm256_storeu_si256(dst.add(copied) as *mut __m256i, chunk);
+            copied += 32;
+        }
+    }
+
+    // two possibly overlapping 16 byte copies cover every length between 16 and 32 without touching bytes after len
+    #[inline(always)]
+    pub unsafe fn copy_16_to_32(src: *const u8, dst: *mut u8, len: usize) {
+        let head = _mm_loadu_si128(src as *const __m128i);
+        let tail = _mm_loadu_si128(src.add(len - 16) as *const __m128i);
+        _mm_storeu_si128(dst as *mut __m128i, head);
+        _mm_storeu_si128(dst.add(len - 16) as *mut __m128i, tail);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn copy_32_to_64(src: *const u8, dst: *mut u8, len: usize) {
+        let head = _mm256_loadu_si256(src as *const __m256i);
+        let tail = _mm256_loadu_si256(src.add(len - 32) as *const __m256i);
+        _mm256_storeu_si256(dst as *mut __m256i, head);
+        _mm256_storeu_si256(dst.add(len - 32) as *mut __m256i, tail);
+    }
+}
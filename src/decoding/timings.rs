@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+/// Time spent in the different parts of block decoding, collected when enabled with FrameDecoder::set_block_timings.
+///
+/// The counters add up over all frames decoded with the same FrameDecoder until they are reset. Measuring costs a few
+/// calls to Instant::now per block, so it is off by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTimings {
+    pub raw: BlockTypeStats,
+    pub rle: BlockTypeStats,
+    /// The time of compressed blocks includes reading the block content and the three phases below
+    pub compressed: BlockTypeStats,
+
+    pub literals_decoding: Duration,
+    pub sequence_decoding: Duration,
+    /// With fused sequence execution decoding and executing can not be told apart, the time of both is counted here
+    pub sequence_execution: Duration,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTypeStats {
+    pub blocks: u64,
+    pub decompressed_bytes: u64,
+    pub time: Duration,
+}
+
+pub enum Phase {
+    LiteralsDecoding,
+    SequenceDecoding,
+    SequenceExecution,
+}
+
+impl BlockTimings {
+    /// Sum of the time spent in all block types
+    pub fn total_time(&self) -> Duration {
+        self.raw.time + self.rle.time + self.compressed.time
+    }
+}
+
+/// Returns the start time if timings are collected
+pub fn start(timings: &Option<BlockTimings>) -> Option<Instant> {
+    timings.as_ref().map(|_| Instant::now())
+}
+
+pub fn record_phase(timings: &mut Option<BlockTimings>, phase: Phase, start: Option<Instant>) {
+    if let (Some(timings), Some(start)) = (timings, start) {
+        let counter = match phase {
+            Phase::LiteralsDecoding => &mut timings.literals_decoding,
+            Phase::SequenceDecoding => &mut timings.sequence_decoding,
+            Phase::SequenceExecution => &mut timings.sequence_execution,
+        };
+        *counter += start.elapsed();
+    }
+}
+
+pub fn record_block(
+    stats: impl FnOnce(&mut BlockTimings) -> &mut BlockTypeStats,
+    timings: &mut Option<BlockTimings>,
+    decompressed_bytes: u64,
+    start: Option<Instant>,
+) {
+    if let (Some(timings), Some(start)) = (timings, start) {
+        let stats = stats(timings);
+        stats.blocks += 1;
+        stats.decompressed_bytes += decompressed_bytes;
+        stats.time += start.elapsed();
+    }
+}
@@ -7,7 +7,7 @@ pub enum FrameDecoderError {
     FailedToInitialize(String),
     FailedToDrainDecodebuffer,
     TargetTooSmall,
-    DictNotProvided,
+    DictNotProvided { dict_id: u32 },
 }
 
 impl std::fmt::Display for FrameDecoderError {
@@ -34,9 +34,10 @@ impl std::fmt::Display for FrameDecoderError {
                 f,
                 "Target must have at least as many bytes as the contentsize of the frame reports"
             ),
-            FrameDecoderError::DictNotProvided => write!(
+            FrameDecoderError::DictNotProvided { dict_id } => write!(
                 f,
-                "Frame header specified dictionary id that wasnt provided by add_dict() or reset_with_dict()"
+                "Frame header specified dictionary id: {} that wasnt provided by add_dict(), reset_with_dict() or the dict provider",
+                dict_id
             ),
         }
     }
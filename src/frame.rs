@@ -1,7 +1,89 @@
+use std::io::{Read, Write};
+
 pub const MAGIC_NUM: u32 = 0xFD2F_B528;
+/// Skippable frames have a magic number in the range 0x184D2A50..=0x184D2A5F
+pub const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFF_FFF0;
+pub const SKIPPABLE_MAGIC_NUM: u32 = 0x184D_2A50;
 pub const MIN_WINDOW_SIZE: u64 = 1024;
 pub const MAX_WINDOW_SIZE: u64 = (1 << 41) + 7 * (1 << 38);
 
+pub fn is_skippable(magic_num: u32) -> bool {
+    magic_num & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC_NUM
+}
+
+/// What comes next in a stream of concatenated frames, see read_frame_start()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameStart {
+    /// The source has no bytes left
+    End,
+    /// The 8 byte header of a skippable frame has been read, data_size bytes of user data follow
+    Skippable { magic_num: u32, data_size: u32 },
+    /// Any other frame. Its magic number has been read and has to be passed on to read_frame_header
+    Frame { magic: [u8; 4] },
+}
+
+#[derive(Debug)]
+pub enum FrameStartError {
+    Io(std::io::Error),
+    /// The source ended this many bytes after the last frame, too few for a magic number
+    TrailingBytes(usize),
+    /// The source ended inside the header of a skippable frame
+    TruncatedSkippableHeader,
+}
+
+/// Reads the magic number of the next frame and, for skippable frames, their size. A source that ends exactly
+/// between two frames gives FrameStart::End.
+pub fn read_frame_start(source: &mut dyn Read) -> Result<FrameStart, FrameStartError> {
+    let mut magic = [0u8; 4];
+    match read_up_to(source, &mut magic).map_err(FrameStartError::Io)? {
+        0 => return Ok(FrameStart::End),
+        4 => {}
+        x => return Err(FrameStartError::TrailingBytes(x)),
+    }
+
+    let magic_num = crate::decoding::little_endian::read_little_endian_u32(&magic);
+    if !is_skippable(magic_num) {
+        return Ok(FrameStart::Frame { magic });
+    }
+    let mut size = [0u8; 4];
+    match read_up_to(source, &mut size).map_err(FrameStartError::Io)? {
+        4 => Ok(FrameStart::Skippable {
+            magic_num,
+            data_size: crate::decoding::little_endian::read_little_endian_u32(&size),
+        }),
+        _ => Err(FrameStartError::TruncatedSkippableHeader),
+    }
+}
+
+/// Reads and drops the data of a skippable frame. Returns how many bytes were skipped, less than data_size if
+/// the source ended early
+pub fn skip_skippable_data(source: &mut dyn Read, data_size: u32) -> std::io::Result<u64> {
+    std::io::copy(&mut source.take(u64::from(data_size)), &mut std::io::sink())
+}
+
+/// Like read_exact but returns how many bytes could be read instead of failing at the end of source
+fn read_up_to(source: &mut dyn Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match source.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// Whether frames start with MAGIC_NUM. Magicless frames leave it out to save 4 bytes,
+/// like ZSTD_f_zstd1_magicless in the reference implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameFormat {
+    #[default]
+    Zstd1,
+    Zstd1Magicless,
+}
+
 pub struct Frame {
     magic_num: u32,
     pub header: FrameHeader,
@@ -82,7 +164,7 @@ impl FrameHeader {
             let mantissa = self.window_descriptor & 0x7;
 
             let window_log = 10 + (exp as u64);
-            let window_base = 1 << (window_log as u64);
+            let window_base = 1 << window_log;
             let window_add = (window_base / 8) * (mantissa as u64);
 
             let window_size = window_base + window_add;
@@ -199,6 +281,162 @@ impl FrameHeader {
     }
 }
 
+/// Builds a FrameHeader from the values it should describe. The builder picks the smallest encoding for each field.
+///
+/// If no window size is set the frame is a single segment frame and its window size is the content size.
+#[derive(Debug, Clone, Default)]
+pub struct FrameHeaderBuilder {
+    window_size: Option<u64>,
+    content_size: Option<u64>,
+    dict_id: Option<u32>,
+    content_checksum: bool,
+}
+
+impl FrameHeaderBuilder {
+    /// The window size is rounded up to the next size the window descriptor can represent
+    pub fn window_size(mut self, window_size: Option<u64>) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    pub fn content_size(mut self, content_size: Option<u64>) -> Self {
+        self.content_size = content_size;
+        self
+    }
+
+    /// A dict id of 0 means no dictionary, same as None
+    pub fn dict_id(mut self, dict_id: Option<u32>) -> Self {
+        self.dict_id = dict_id;
+        self
+    }
+
+    pub fn content_checksum(mut self, content_checksum: bool) -> Self {
+        self.content_checksum = content_checksum;
+        self
+    }
+
+    pub fn build(self) -> Result<FrameHeader, String> {
+        let single_segment = self.window_size.is_none();
+        if single_segment && self.content_size.is_none() {
+            return Err(
+                "Frame needs either a window size or a content size (single segment)".to_owned(),
+            );
+        }
+
+        let (fcs_flag, frame_content_size) = match self.content_size {
+            None => (0, Vec::new()),
+            Some(x) if single_segment && x < 256 => (0, vec![x as u8]),
+            Some(x) if (256..256 + (1 << 16)).contains(&x) => {
+                (1, ((x - 256) as u16).to_le_bytes().to_vec())
+            }
+            Some(x) if x <= u64::from(u32::MAX) => (2, (x as u32).to_le_bytes().to_vec()),
+            Some(x) => (3, x.to_le_bytes().to_vec()),
+        };
+
+        let (dict_flag, dict_id) = match self.dict_id {
+            None | Some(0) => (0, Vec::new()),
+            Some(x) if x < (1 << 8) => (1, vec![x as u8]),
+            Some(x) if x < (1 << 16) => (2, (x as u16).to_le_bytes().to_vec()),
+            Some(x) => (3, x.to_le_bytes().to_vec()),
+        };
+
+        let window_descriptor = match self.window_size {
+            Some(x) => encode_window_descriptor(x)?,
+            None => 0,
+        };
+
+        let header = FrameHeader {
+            descriptor: FrameDescriptor(
+                (fcs_flag << 6)
+                    | (u8::from(single_segment) << 5)
+                    | (u8::from(self.content_checksum) << 2)
+                    | dict_flag,
+            ),
+            window_descriptor,
+            dict_id,
+            frame_content_size,
+        };
+        // the window descriptor might encode a window that is too big
+        header.window_size()?;
+        Ok(header)
+    }
+}
+
+/// Smallest window descriptor whose window size is >= window_size
+fn encode_window_descriptor(window_size: u64) -> Result<u8, String> {
+    let window_size = u64::max(window_size, MIN_WINDOW_SIZE);
+    let window_log = 63 - u64::leading_zeros(window_size) as u64;
+    let window_base = 1u64 << window_log;
+    let step = window_base / 8;
+    let mut exp = window_log - 10;
+    let mut mantissa = (window_size - window_base).div_ceil(step);
+    if mantissa == 8 {
+        exp += 1;
+        mantissa = 0;
+    }
+    if exp > 31 {
+        return Err(format!(
+            "window_size bigger than allowed maximum. Is: {}, Should be lower than: {}",
+            window_size, MAX_WINDOW_SIZE
+        ));
+    }
+    Ok(((exp as u8) << 3) | mantissa as u8)
+}
+
+impl FrameHeader {
+    pub fn builder() -> FrameHeaderBuilder {
+        FrameHeaderBuilder::default()
+    }
+
+    /// A builder with the values of this header, e.g. to strip the dict id or add the content size
+    pub fn to_builder(&self) -> Result<FrameHeaderBuilder, String> {
+        let window_size = if self.descriptor.single_segment_flag() {
+            None
+        } else {
+            Some(self.window_size()?)
+        };
+        let content_size = if self.descriptor.frame_content_size_bytes()? == 0 {
+            None
+        } else {
+            Some(self.frame_content_size()?)
+        };
+        Ok(FrameHeaderBuilder {
+            window_size,
+            content_size,
+            dict_id: self.dictiornary_id()?,
+            content_checksum: self.descriptor.content_checksum_flag(),
+        })
+    }
+
+    /// Writes the magic number and the header exactly like read_frame_header expects them. Returns the amount of bytes written.
+    pub fn write_to(&self, w: &mut impl Write) -> Result<u8, String> {
+        self.write_to_with_format(w, FrameFormat::Zstd1)
+    }
+
+    /// Like write_to but leaves out the magic number for FrameFormat::Zstd1Magicless
+    pub fn write_to_with_format(
+        &self,
+        w: &mut impl Write,
+        format: FrameFormat,
+    ) -> Result<u8, String> {
+        let mut raw = Vec::with_capacity(18);
+        if format == FrameFormat::Zstd1 {
+            raw.extend(MAGIC_NUM.to_le_bytes());
+        }
+        raw.push(self.descriptor.0);
+        if !self.descriptor.single_segment_flag() {
+            raw.push(self.window_descriptor);
+        }
+        raw.extend(&self.dict_id);
+        raw.extend(&self.frame_content_size);
+
+        match w.write_all(&raw) {
+            Ok(_) => Ok(raw.len() as u8),
+            Err(e) => Err(format!("Error while writing frame header: {}", e)),
+        }
+    }
+}
+
 impl Frame {
     pub fn check_valid(&self) -> Result<(), String> {
         if self.magic_num != MAGIC_NUM {
@@ -229,16 +467,30 @@ impl Frame {
     }
 }
 
-use std::io::Read;
 pub fn read_frame_header(r: &mut dyn Read) -> Result<(Frame, u8), String> {
+    read_frame_header_with_format(r, FrameFormat::Zstd1)
+}
+
+/// Like read_frame_header but for magicless frames the header starts directly with the frame descriptor.
+/// The returned size does not include the magic number then.
+pub fn read_frame_header_with_format(
+    r: &mut dyn Read,
+    format: FrameFormat,
+) -> Result<(Frame, u8), String> {
     let mut buf = [0u8; 4];
-    let magic_num: u32 = match r.read_exact(&mut buf[0..4]) {
-        Ok(_) => crate::decoding::little_endian::read_little_endian_u32(&buf[..]),
-        Err(_) => return Err("Error while reading magic number".to_owned()),
+    let mut bytes_read = 0;
+    let magic_num: u32 = match format {
+        FrameFormat::Zstd1 => {
+            bytes_read += 4;
+            match r.read_exact(&mut buf[0..4]) {
+                Ok(_) => crate::decoding::little_endian::read_little_endian_u32(&buf[..]),
+                Err(_) => return Err("Error while reading magic number".to_owned()),
+            }
+        }
+        // the frame is implicitly a zstd frame
+        FrameFormat::Zstd1Magicless => MAGIC_NUM,
     };
 
-    let mut bytes_read = 4;
-
     let desc: FrameDescriptor = match r.read_exact(&mut buf[0..1]) {
         Ok(_) => FrameDescriptor(buf[0]),
         Err(_) => return Err("Error while reading frame descriptor".to_owned()),
@@ -250,21 +502,13 @@ pub fn read_frame_header(r: &mut dyn Read) -> Result<(Frame, u8), String> {
         descriptor: FrameDescriptor(desc.0),
         dict_id: match desc.dictionary_id_bytes() {
             Ok(bytes) => {
-                let mut v = Vec::with_capacity(bytes as usize);
-                for _ in 0..bytes {
-                    v.push(0);
-                }
-                v
+                vec![0; bytes as usize]
             }
             Err(m) => return Err(m),
         },
         frame_content_size: match desc.frame_content_size_bytes() {
             Ok(bytes) => {
-                let mut v = Vec::with_capacity(bytes as usize);
-                for _ in 0..bytes {
-                    v.push(0);
-                }
-                v
+                vec![0; bytes as usize]
             }
             Err(m) => return Err(m),
         },
@@ -280,7 +524,7 @@ pub fn read_frame_header(r: &mut dyn Read) -> Result<(Frame, u8), String> {
     }
 
     if !frame_header.dict_id.is_empty() {
-        match r.read_exact(&mut frame_header.dict_id.as_mut_slice()) {
+        match r.read_exact(frame_header.dict_id.as_mut_slice()) {
             Ok(_) => {}
             Err(_) => return Err("Error while reading dcitionary id".to_owned()),
         }
@@ -288,7 +532,7 @@ pub fn read_frame_header(r: &mut dyn Read) -> Result<(Frame, u8), String> {
     }
 
     if !frame_header.frame_content_size.is_empty() {
-        match r.read_exact(&mut frame_header.frame_content_size.as_mut_slice()) {
+        match r.read_exact(frame_header.frame_content_size.as_mut_slice()) {
             Ok(_) => {}
             Err(_) => return Err("Error while reading frame content size".to_owned()),
         }
@@ -1,10 +1,13 @@
 use super::frame;
 use crate::decoding;
-use crate::decoding::dictionary::Dictionary;
+use crate::decoding::dictionary::{Dictionary, DictionaryProvider};
 use crate::decoding::scratch::DecoderScratch;
+use crate::decoding::simd_copy::CopyStrategy;
+use crate::decoding::timings::BlockTimings;
 use std::collections::HashMap;
 use std::hash::Hasher;
 use std::io::Read;
+use std::sync::Arc;
 
 /// This implements a decoder for zstd frames. This decoder is able to decode frames only partially and gives control
 /// over how many bytes/blocks will be decoded at a time (so you dont have to decode a 10GB file into memory all at once).
@@ -24,37 +27,59 @@ use std::io::Read;
 ///     //Create a new decoder
 ///     let mut frame_dec = ruzstd::FrameDecoder::new();
 ///     let mut result = Vec::new();
-/// 
+///
 ///     // Use reset or init to make the decoder ready to decocde the frame from the io::Read
 ///     frame_dec.reset(file).unwrap();
-/// 
+///
 ///     // Loop until the frame has been decoded completely
 ///     while !frame_dec.is_finished() {
 ///         // decode (roughly) batch_size many bytes
 ///         frame_dec.decode_blocks(file, BlockDecodingStrategy::UptoBytes(1024)).unwrap();
-/// 
+///
 ///         // read from the decoder to collect bytes from the internal buffer
 ///         let bytes_read = frame_dec.read(result.as_mut_slice()).unwrap();
 ///         
 ///         // then do something with it
 ///         do_something(&result[0..bytes_read]);
 ///     }
-/// 
+///
 ///     // handle the last chunk of data
 ///     while frame_dec.can_collect() > 0 {
 ///         let x = frame_dec.read(result.as_mut_slice()).unwrap();
-/// 
+///
 ///         do_something(&result[0..x]);
 ///     }
 /// }
-/// 
+///
 /// fn do_something(data: &[u8]) {
 ///     std::io::stdout().write_all(data).unwrap();
 /// }
 /// ```
 pub struct FrameDecoder {
     state: Option<FrameDecoderState>,
-    dicts: HashMap<u32, Dictionary>,
+    dicts: HashMap<u32, Arc<Dictionary>>,
+    dict_provider: Option<Arc<dyn DictionaryProvider>>,
+    fuse_sequence_execution: bool,
+    copy_strategy: CopyStrategy,
+    frame_format: frame::FrameFormat,
+    collect_block_timings: bool,
+    max_window_size: u64,
+    prefix: Option<Arc<[u8]>>,
+    progress_callback: Option<ProgressCallback>,
+}
+
+type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+
+/// How far decoding the current frame has come, passed to the callback set with FrameDecoder::set_progress_callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of this frame read from the source so far, including the header. Same as bytes_read_from_source()
+    pub bytes_read: u64,
+    /// Bytes of this frame decoded so far, whether they have been collected or not
+    pub bytes_decoded: u64,
+    pub blocks_decoded: usize,
+    /// The decompressed size of the frame if the header contains it
+    pub content_size: Option<u64>,
 }
 
 struct FrameDecoderState {
@@ -63,6 +88,7 @@ struct FrameDecoderState {
     frame_finished: bool,
     block_counter: usize,
     bytes_read_counter: u64,
+    bytes_decoded_counter: u64,
     check_sum: Option<u32>,
     using_dict: Option<u32>,
 }
@@ -73,45 +99,71 @@ pub enum BlockDecodingStrategy {
     UptoBytes(usize),
 }
 
-const MAX_WINDOW_SIZE: u64 = 1024 * 1024 * 100;
+/// Default for the biggest window size a FrameDecoder accepts, see FrameDecoder::set_max_window_size
+pub const MAX_WINDOW_SIZE: u64 = 1024 * 1024 * 100;
+
+fn check_window_size(window_size: u64, max_window_size: u64) -> Result<(), String> {
+    if window_size > max_window_size {
+        return Err(format!(
+            "Dont support window_sizes (requested: {}) over: {}",
+            window_size, max_window_size
+        ));
+    }
+    Ok(())
+}
 
 impl FrameDecoderState {
-    pub fn new(source: &mut dyn Read) -> Result<FrameDecoderState, String> {
-        let (frame, header_size) = frame::read_frame_header(source)?;
+    pub fn new(
+        source: &mut dyn Read,
+        format: frame::FrameFormat,
+        max_window_size: u64,
+    ) -> Result<FrameDecoderState, String> {
+        let (frame, header_size) = frame::read_frame_header_with_format(source, format)?;
         let window_size = frame.header.window_size()?;
         frame.check_valid()?;
+        check_window_size(window_size, max_window_size)?;
         Ok(FrameDecoderState {
             frame,
             frame_finished: false,
             block_counter: 0,
             decoder_scratch: DecoderScratch::new(window_size as usize),
             bytes_read_counter: u64::from(header_size),
+            bytes_decoded_counter: 0,
             check_sum: None,
             using_dict: None,
         })
     }
 
-    pub fn reset(&mut self, source: &mut dyn Read) -> Result<(), String> {
-        let (frame, header_size) = frame::read_frame_header(source)?;
+    pub fn reset(
+        &mut self,
+        source: &mut dyn Read,
+        format: frame::FrameFormat,
+        max_window_size: u64,
+    ) -> Result<(), String> {
+        let (frame, header_size) = frame::read_frame_header_with_format(source, format)?;
         let window_size = frame.header.window_size()?;
         frame.check_valid()?;
-
-        if window_size > MAX_WINDOW_SIZE {
-            return Err(format!(
-                "Dont support window_sizes (requested: {}) over: {}",
-                window_size, MAX_WINDOW_SIZE
-            ));
-        }
+        check_window_size(window_size, max_window_size)?;
 
         self.frame = frame;
         self.frame_finished = false;
         self.block_counter = 0;
         self.decoder_scratch.reset(window_size as usize);
         self.bytes_read_counter = u64::from(header_size);
+        self.bytes_decoded_counter = 0;
         self.check_sum = None;
         self.using_dict = None;
         Ok(())
     }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            bytes_read: self.bytes_read_counter,
+            bytes_decoded: self.bytes_decoded_counter,
+            blocks_decoded: self.block_counter,
+            content_size: self.frame.header.frame_content_size().ok(),
+        }
+    }
 }
 
 impl Default for FrameDecoder {
@@ -128,7 +180,29 @@ impl FrameDecoder {
         FrameDecoder {
             state: None,
             dicts: HashMap::new(),
+            dict_provider: None,
+            fuse_sequence_execution: false,
+            copy_strategy: CopyStrategy::detect(),
+            frame_format: frame::FrameFormat::Zstd1,
+            collect_block_timings: false,
+            max_window_size: MAX_WINDOW_SIZE,
+            prefix: None,
+            progress_callback: None,
+        }
+    }
+
+    /// Drops all settings and dicts so the decoder behaves like one fresh from new(), but keeps the buffers of the
+    /// last frame for reuse. DecoderPool calls this before handing a decoder out again
+    pub(crate) fn restore_defaults(&mut self) {
+        let mut state = self.state.take();
+        let mut dicts = std::mem::take(&mut self.dicts);
+        dicts.clear();
+        if let Some(state) = &mut state {
+            state.decoder_scratch.timings = None;
         }
+        *self = FrameDecoder::new();
+        self.state = state;
+        self.dicts = dicts;
     }
 
     /// init() will allocate all needed buffers if it is the first time this decoder is used
@@ -152,13 +226,37 @@ impl FrameDecoder {
     ///
     /// equivalent to init()
     pub fn reset(&mut self, source: &mut dyn Read) -> Result<(), String> {
+        // the window has to cover the prefix, zstd --patch-from makes it at least the next power of two
+        let max_window_size = match &self.prefix {
+            Some(prefix) => self
+                .max_window_size
+                .max((prefix.len() as u64 + 1).next_power_of_two()),
+            None => self.max_window_size,
+        };
         match &mut self.state {
-            Some(s) => s.reset(source),
+            Some(s) => s.reset(source, self.frame_format, max_window_size)?,
             None => {
-                self.state = Some(FrameDecoderState::new(source)?);
-                Ok(())
+                self.state = Some(FrameDecoderState::new(
+                    source,
+                    self.frame_format,
+                    max_window_size,
+                )?)
             }
         }
+        if let Some(state) = &mut self.state {
+            if let (Some(prefix), Ok(None)) = (&self.prefix, state.frame.header.dictiornary_id()) {
+                state.decoder_scratch.buffer.prefix = Some(Arc::clone(prefix));
+            }
+            state.decoder_scratch.fuse_sequence_execution = self.fuse_sequence_execution;
+            if self.collect_block_timings && state.decoder_scratch.timings.is_none() {
+                state.decoder_scratch.timings = Some(BlockTimings::default());
+            }
+            state
+                .decoder_scratch
+                .buffer
+                .set_copy_strategy(self.copy_strategy);
+        }
+        Ok(())
     }
 
     /// Like reset but provides the dict to use for the next frame
@@ -174,10 +272,115 @@ impl FrameDecoder {
     /// Add a dict to the FrameDecoder that can be used when needed. The FrameDecoder uses the appropriate one dynamically
     pub fn add_dict(&mut self, raw_dict: &[u8]) -> Result<(), String> {
         let dict = Dictionary::decode_dict(raw_dict)?;
-        self.dicts.insert(dict.id, dict);
+        self.add_shared_dict(Arc::new(dict));
         Ok(())
     }
 
+    /// Like add_dict but takes an already parsed dict. This allows parsing a dict once and sharing it between many
+    /// FrameDecoders (possibly on different threads) without copying it for each decoder
+    pub fn add_shared_dict(&mut self, dict: Arc<Dictionary>) {
+        self.dicts.insert(dict.id, dict);
+    }
+
+    /// Set a provider that is asked for dicts that were not added with add_dict/add_shared_dict. The provider is
+    /// called with the dict id every time a frame needs a dict that is unknown to this decoder, it should cache
+    /// dicts itself if loading them is expensive.
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_dict_provider(&mut self, provider: Arc<dyn DictionaryProvider>) {
+        self.dict_provider = Some(provider);
+    }
+
+    /// Choose whether sequences get executed directly while they are decoded (true) or if all sequences of a block
+    /// are decoded first and executed afterwards (false, the default). Both produce the same output, the fused
+    /// variant avoids buffering the sequences and walking them a second time.
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_fused_sequence_execution(&mut self, fused: bool) {
+        self.fuse_sequence_execution = fused;
+        if let Some(state) = &mut self.state {
+            state.decoder_scratch.fuse_sequence_execution = fused;
+        }
+    }
+
+    /// Choose whether the decoder measures how long the different parts of block decoding take. The counters start
+    /// at zero whenever this is called and add up over all following frames, see block_timings()
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_block_timings(&mut self, enabled: bool) {
+        self.collect_block_timings = enabled;
+        if let Some(state) = &mut self.state {
+            state.decoder_scratch.timings = if enabled {
+                Some(BlockTimings::default())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// The timings collected since set_block_timings(true) was called. None if timings are not collected
+    pub fn block_timings(&self) -> Option<BlockTimings> {
+        if !self.collect_block_timings {
+            return None;
+        }
+        match &self.state {
+            None => Some(BlockTimings::default()),
+            Some(state) => state.decoder_scratch.timings,
+        }
+    }
+
+    /// Use prefix as the history before each frame that does not name a dictionary, like a dictionary that only has
+    /// content. This is how patches made with zstd --patch-from=reference are decoded, the prefix is the reference.
+    /// The allowed window size is raised so it covers the prefix, see set_max_window_size().
+    ///
+    /// This setting is kept across init()/reset() calls and takes effect with the next one
+    pub fn set_prefix(&mut self, prefix: impl Into<Arc<[u8]>>) {
+        self.prefix = Some(prefix.into());
+    }
+
+    pub fn clear_prefix(&mut self) {
+        self.prefix = None;
+    }
+
+    /// Call callback after each decoded block with the progress made in the current frame. This can be used to show
+    /// a progress bar or to abort decoding by stopping to call decode_blocks from the outside.
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_progress_callback(&mut self, callback: impl FnMut(&Progress) + Send + 'static) {
+        self.progress_callback = Some(Box::new(callback));
+    }
+
+    pub fn clear_progress_callback(&mut self) {
+        self.progress_callback = None;
+    }
+
+    /// Frames with a bigger window size are rejected by init()/reset(), because the decoder needs to keep
+    /// window size bytes in memory. The default is MAX_WINDOW_SIZE.
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_max_window_size(&mut self, max_window_size: u64) {
+        self.max_window_size = max_window_size;
+    }
+
+    /// Choose how the decoder copies matches and literals. By default the best strategy supported by the cpu is used
+    /// (SIMD strategies need the "simd" feature). Strategies the cpu does not support are replaced by the best supported one.
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_copy_strategy(&mut self, strategy: CopyStrategy) {
+        self.copy_strategy = strategy.or_supported();
+        if let Some(state) = &mut self.state {
+            state.decoder_scratch.buffer.set_copy_strategy(strategy);
+        }
+    }
+
+    /// Choose whether the frames this decoder reads start with the magic number (FrameFormat::Zstd1, the default)
+    /// or not (FrameFormat::Zstd1Magicless). Takes effect with the next init()/reset()
+    ///
+    /// This setting is kept across init()/reset() calls
+    pub fn set_frame_format(&mut self, format: frame::FrameFormat) {
+        self.frame_format = format;
+    }
+
     /// Returns how many bytes the frame contains after decompression
     pub fn content_size(&self) -> Option<u64> {
         let state = match &self.state {
@@ -185,10 +388,7 @@ impl FrameDecoder {
             Some(s) => s,
         };
 
-        match state.frame.header.frame_content_size() {
-            Err(_) => None,
-            Ok(x) => Some(x),
-        }
+        state.frame.header.frame_content_size().ok()
     }
 
     /// Returns the checksum that was read from the data. Only available after all bytes have been read. It is the last 4 bytes of a zstd-frame
@@ -268,11 +468,8 @@ impl FrameDecoder {
                         debug_assert!(id == using_id);
                     }
                     None => {
-                        let dict = match self.dicts.get(&id) {
-                            Some(dict) => dict,
-                            None => return Err(crate::errors::FrameDecoderError::DictNotProvided),
-                        };
-                        state.decoder_scratch.use_dict(dict);
+                        let dict = lookup_dict(&self.dicts, &self.dict_provider, id)?;
+                        state.decoder_scratch.use_dict(&dict);
                         state.using_dict = Some(id);
                     }
                 }
@@ -310,15 +507,26 @@ impl FrameDecoder {
                 );
             }
 
+            let buffer_size_before_block = state.decoder_scratch.buffer.len();
             let bytes_read_in_block_body = match block_dec.decode_block_content(
                 &block_header,
                 &mut state.decoder_scratch,
                 source,
             ) {
                 Ok(h) => h,
-                Err(m) => return Err(crate::errors::FrameDecoderError::FailedToReadBlockBody(m)),
+                Err(m) => {
+                    // only keep output of complete blocks so it can still be collected with collect_all()
+                    state
+                        .decoder_scratch
+                        .buffer
+                        .buffer
+                        .truncate(buffer_size_before_block);
+                    return Err(crate::errors::FrameDecoderError::FailedToReadBlockBody(m));
+                }
             };
             state.bytes_read_counter += bytes_read_in_block_body;
+            state.bytes_decoded_counter +=
+                (state.decoder_scratch.buffer.len() - buffer_size_before_block) as u64;
 
             state.block_counter += 1;
 
@@ -342,6 +550,13 @@ impl FrameDecoder {
                         }
                     };
                 }
+            }
+
+            if let Some(callback) = &mut self.progress_callback {
+                callback(&state.progress());
+            }
+
+            if block_header.last_block {
                 break;
             }
 
@@ -378,6 +593,17 @@ impl FrameDecoder {
         }
     }
 
+    /// Collect all bytes in the decodebuffer, including the window_size bytes that collect() retains while decoding is going on.
+    /// After decode_blocks returned an error this returns the output of all blocks that were decoded completely.
+    /// Decoding the current frame can not continue after this, the next frame needs init()/reset() first.
+    pub fn collect_all(&mut self) -> Option<Vec<u8>> {
+        let state = match &mut self.state {
+            None => return None,
+            Some(s) => s,
+        };
+        Some(state.decoder_scratch.buffer.drain())
+    }
+
     /// Collect bytes and retain window_size bytes while decoding is still going on.
     /// After decoding of the frame (is_finished() == true) has finished it will collect all remaining bytes
     pub fn collect_to_writer(
@@ -408,10 +634,11 @@ impl FrameDecoder {
         if finished {
             state.decoder_scratch.buffer.can_drain()
         } else {
-            match state.decoder_scratch.buffer.can_drain_to_window_size() {
-                Some(x) => x,
-                None => 0,
-            }
+            state
+                .decoder_scratch
+                .buffer
+                .can_drain_to_window_size()
+                .unwrap_or(0)
         }
     }
 
@@ -439,7 +666,7 @@ impl FrameDecoder {
         };
 
         if !self.is_finished() || self.state.is_none() {
-            let mut mt_source = &source[..];
+            let mut mt_source = source;
 
             if self.state.is_none() {
                 match self.init(&mut mt_source) {
@@ -450,7 +677,7 @@ impl FrameDecoder {
 
             //pseudo block to scope "state" so we can borrow self again after the block
             {
-                let mut state = match &mut self.state {
+                let state = match &mut self.state {
                     Some(s) => s,
                     None => panic!("Bug in library"),
                 };
@@ -478,15 +705,8 @@ impl FrameDecoder {
                                 debug_assert!(id == using_id);
                             }
                             None => {
-                                let dict = match self.dicts.get(&id) {
-                                    Some(dict) => dict,
-                                    None => {
-                                        return Err(
-                                            crate::errors::FrameDecoderError::DictNotProvided,
-                                        )
-                                    }
-                                };
-                                state.decoder_scratch.use_dict(dict);
+                                let dict = lookup_dict(&self.dicts, &self.dict_provider, id)?;
+                                state.decoder_scratch.use_dict(&dict);
                                 state.using_dict = Some(id);
                             }
                         }
@@ -520,6 +740,7 @@ impl FrameDecoder {
                     }
                     state.bytes_read_counter += u64::from(block_header_size);
 
+                    let buffer_size_before_block = state.decoder_scratch.buffer.len();
                     let bytes_read_in_block_body = match block_dec.decode_block_content(
                         &block_header,
                         &mut state.decoder_scratch,
@@ -531,6 +752,8 @@ impl FrameDecoder {
                         }
                     };
                     state.bytes_read_counter += bytes_read_in_block_body;
+                    state.bytes_decoded_counter +=
+                        (state.decoder_scratch.buffer.len() - buffer_size_before_block) as u64;
                     state.block_counter += 1;
 
                     if block_header.last_block {
@@ -545,6 +768,13 @@ impl FrameDecoder {
                                 state.check_sum = Some(chksum);
                             }
                         }
+                    }
+
+                    if let Some(callback) = &mut self.progress_callback {
+                        callback(&state.progress());
+                    }
+
+                    if block_header.last_block {
                         break;
                     }
                 }
@@ -579,3 +809,23 @@ impl std::io::Read for FrameDecoder {
         }
     }
 }
+
+fn lookup_dict(
+    dicts: &HashMap<u32, Arc<Dictionary>>,
+    provider: &Option<Arc<dyn DictionaryProvider>>,
+    id: u32,
+) -> Result<Arc<Dictionary>, crate::errors::FrameDecoderError> {
+    if let Some(dict) = dicts.get(&id) {
+        return Ok(Arc::clone(dict));
+    }
+    match provider.as_ref().and_then(|p| p.get_dict(id)) {
+        Some(dict) if dict.id == id => Ok(dict),
+        Some(dict) => Err(crate::errors::FrameDecoderError::FailedToInitialize(
+            format!(
+                "Dict provider returned dict with id: {} for requested id: {}",
+                dict.id, id
+            ),
+        )),
+        None => Err(crate::errors::FrameDecoderError::DictNotProvided { dict_id: id }),
+    }
+}
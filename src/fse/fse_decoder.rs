@@ -1,7 +1,6 @@
 use crate::decoding::bit_reader::BitReader;
 use crate::decoding::bit_reader_reverse::BitReaderReversed;
 
-#[derive(Clone)]
 pub struct FSETable {
     pub decode: Vec<Entry>, //used to decode symbols, and calculate the next state
 
@@ -10,6 +9,26 @@ pub struct FSETable {
     symbol_counter: Vec<u32>,
 }
 
+// Implemented by hand so clone_from can reuse the allocations of the target table
+impl Clone for FSETable {
+    fn clone(&self) -> Self {
+        FSETable {
+            decode: self.decode.clone(),
+            accuracy_log: self.accuracy_log,
+            symbol_probablilities: self.symbol_probablilities.clone(),
+            symbol_counter: self.symbol_counter.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.decode.clone_from(&source.decode);
+        self.accuracy_log = source.accuracy_log;
+        self.symbol_probablilities
+            .clone_from(&source.symbol_probablilities);
+        self.symbol_counter.clone_from(&source.symbol_counter);
+    }
+}
+
 impl Default for FSETable {
     fn default() -> Self {
         Self::new()
@@ -40,7 +59,7 @@ fn highest_bit_set(x: u32) -> u32 {
 }
 
 impl<'t> FSEDecoder<'t> {
-    pub fn new(table: &'t FSETable) -> FSEDecoder {
+    pub fn new(table: &'t FSETable) -> FSEDecoder<'t> {
         FSEDecoder { state: 0, table }
     }
 
@@ -112,7 +131,7 @@ impl FSETable {
 
         let table_size = 1 << self.accuracy_log;
         if self.decode.len() < table_size {
-            self.decode.reserve(table_size as usize - self.decode.len());
+            self.decode.reserve(table_size - self.decode.len());
         }
         //fill with dummy entries
         self.decode.resize(
@@ -149,7 +168,7 @@ impl FSETable {
             let prob = self.symbol_probablilities[idx];
             for _ in 0..prob {
                 let entry = &mut self.decode[position];
-                entry.symbol = symbol as u8;
+                entry.symbol = symbol;
 
                 position = next_position(position, table_size);
                 while position >= negative_idx {
@@ -169,8 +188,7 @@ impl FSETable {
             let prob = self.symbol_probablilities[symbol as usize];
 
             let symbol_count = self.symbol_counter[symbol as usize];
-            let (bl, nb) =
-                calc_baseline_and_numbits(table_size as u32, prob as u32, symbol_count as u32);
+            let (bl, nb) = calc_baseline_and_numbits(table_size as u32, prob as u32, symbol_count);
 
             //println!("symbol: {:2}, table: {}, prob: {:3}, count: {:3}, bl: {:3}, nb: {:2}", symbol, table_size, prob, symbol_count, bl, nb);
 
@@ -206,7 +224,7 @@ impl FSETable {
 
             let unchecked_value = br.get_bits(bits_to_read as usize)? as u32;
 
-            let low_threshold = ((1 << bits_to_read) - 1) - (max_remaining_value as u32);
+            let low_threshold = ((1 << bits_to_read) - 1) - max_remaining_value;
             let mask = (1 << (bits_to_read - 1)) - 1;
             let small_value = unchecked_value & mask;
 
@@ -256,12 +274,13 @@ impl FSETable {
             ));
         }
 
+        #[allow(clippy::manual_is_multiple_of)]
         let bytes_read = if br.bits_read() % 8 == 0 {
             br.bits_read() / 8
         } else {
             (br.bits_read() / 8) + 1
         };
-        Ok(bytes_read as usize)
+        Ok(bytes_read)
     }
 }
 
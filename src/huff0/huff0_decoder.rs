@@ -2,7 +2,6 @@ use crate::decoding::bit_reader_reverse::BitReaderReversed;
 use crate::fse::FSEDecoder;
 use crate::fse::FSETable;
 
-#[derive(Clone)]
 pub struct HuffmanTable {
     decode: Vec<Entry>,
 
@@ -56,7 +55,7 @@ impl<'t> HuffmanDecoder<'t> {
     pub fn init_state(&mut self, br: &mut BitReaderReversed) -> Result<u8, String> {
         let num_bits = self.table.max_num_bits;
         let new_bits = br.get_bits(num_bits as usize)?;
-        self.state = new_bits as u64;
+        self.state = new_bits;
         Ok(num_bits)
     }
 
@@ -65,11 +64,36 @@ impl<'t> HuffmanDecoder<'t> {
         let new_bits = br.get_bits(num_bits as usize)?;
         self.state <<= num_bits;
         self.state &= self.table.decode.len() as u64 - 1;
-        self.state |= new_bits as u64;
+        self.state |= new_bits;
         Ok(num_bits)
     }
 }
 
+// Implemented by hand so clone_from can reuse the allocations of the target table
+impl Clone for HuffmanTable {
+    fn clone(&self) -> Self {
+        HuffmanTable {
+            decode: self.decode.clone(),
+            weights: self.weights.clone(),
+            max_num_bits: self.max_num_bits,
+            bits: self.bits.clone(),
+            bit_ranks: self.bit_ranks.clone(),
+            rank_indexes: self.rank_indexes.clone(),
+            fse_table: self.fse_table.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.decode.clone_from(&source.decode);
+        self.weights.clone_from(&source.weights);
+        self.max_num_bits = source.max_num_bits;
+        self.bits.clone_from(&source.bits);
+        self.bit_ranks.clone_from(&source.bit_ranks);
+        self.rank_indexes.clone_from(&source.rank_indexes);
+        self.fse_table.clone_from(&source.fse_table);
+    }
+}
+
 impl Default for HuffmanTable {
     fn default() -> Self {
         Self::new()
@@ -139,8 +163,8 @@ impl HuffmanTable {
                 let mut dec1 = FSEDecoder::new(&self.fse_table);
                 let mut dec2 = FSEDecoder::new(&self.fse_table);
 
-                let compressed_start = bytes_used_by_fse_header as usize;
-                let compressed_length = header as usize - bytes_used_by_fse_header as usize;
+                let compressed_start = bytes_used_by_fse_header;
+                let compressed_length = header as usize - bytes_used_by_fse_header;
 
                 let compressed_weights = &fse_stream[compressed_start..];
                 if compressed_weights.len() < compressed_length {
@@ -209,8 +233,9 @@ impl HuffmanTable {
                 let num_weights = header - 127;
                 self.weights.resize(num_weights as usize, 0);
 
+                #[allow(clippy::manual_is_multiple_of)]
                 let bytes_needed = if num_weights % 2 == 0 {
-                    (num_weights as usize / 2)
+                    num_weights as usize / 2
                 } else {
                     (num_weights as usize / 2) + 1
                 };
@@ -253,7 +278,7 @@ impl HuffmanTable {
                     *w, MAX_MAX_NUM_BITS
                 ));
             }
-            weight_sum += if *w > 0 { (1 as u32) << (*w - 1) } else { 0 };
+            weight_sum += if *w > 0 { 1_u32 << (*w - 1) } else { 0 };
         }
 
         if weight_sum == 0 {
@@ -261,7 +286,7 @@ impl HuffmanTable {
         }
 
         let max_bits = highest_bit_set(weight_sum) as u8;
-        let left_over = ((1 as u32) << max_bits) - weight_sum;
+        let left_over = (1_u32 << max_bits) - weight_sum;
 
         //left_over must be power of two
         if left_over & (left_over - 1) != 0 {
@@ -319,11 +344,9 @@ impl HuffmanTable {
 
         assert!(
             self.rank_indexes[0] == self.decode.len(),
-            format!(
-                "rank_idx[0]: {} should be: {}",
-                self.rank_indexes[0],
-                self.decode.len()
-            )
+            "rank_idx[0]: {} should be: {}",
+            self.rank_indexes[0],
+            self.decode.len()
         );
 
         for symbol in 0..self.bits.len() {
@@ -0,0 +1,310 @@
+use crate::block::block::BlockType;
+use crate::block::literals_section::{LiteralsSection, LiteralsSectionType};
+use crate::block::sequence_section::{ModeType, SequencesHeader};
+use crate::decoding::block_decoder;
+use crate::frame::{self, FrameStart, FrameStartError};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Everything that can be learned about a zstd frame without decompressing it
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    /// Byte offset of the frame in the inspected source
+    pub offset: u64,
+    pub header_size: u8,
+    /// Size of the whole frame including header, blocks and checksum
+    pub compressed_size: u64,
+    pub window_size: u64,
+    pub dict_id: Option<u32>,
+    /// The decompressed size if the frame header contains it
+    pub content_size: Option<u64>,
+    pub single_segment: bool,
+    pub checksum_flag: bool,
+    /// The checksum stored at the end of the frame if the checksum flag is set
+    pub checksum: Option<u32>,
+    pub block_count: usize,
+    /// Only filled if block details were requested
+    pub blocks: Vec<BlockInfo>,
+}
+
+/// A skippable frame. Its content is not interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkippableFrameInfo {
+    pub offset: u64,
+    pub magic_num: u32,
+    /// Size of the user data, not including the 8 byte header
+    pub data_size: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum InspectedFrame {
+    Zstd(FrameInfo),
+    Skippable(SkippableFrameInfo),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// Byte offset of the block header in the inspected source
+    pub offset: u64,
+    pub block_type: BlockType,
+    pub last_block: bool,
+    /// Size of the block content as stored in the frame, not including the 3 byte block header
+    pub compressed_size: u32,
+    /// Known for raw and RLE blocks and for compressed blocks without sequences
+    pub decompressed_size: Option<u32>,
+    /// Only present for compressed blocks
+    pub literals: Option<LiteralsInfo>,
+    /// Only present for compressed blocks
+    pub sequences: Option<SequencesInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteralsInfo {
+    pub ls_type: LiteralsSectionType,
+    pub regenerated_size: u32,
+    pub compressed_size: Option<u32>,
+    pub num_streams: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencesInfo {
+    pub num_sequences: u32,
+    /// The modes are None if there are no sequences
+    pub ll_mode: Option<ModeType>,
+    pub of_mode: Option<ModeType>,
+    pub ml_mode: Option<ModeType>,
+}
+
+impl FrameInfo {
+    /// Sum of the known decompressed sizes. Only exact if the content size is in the header
+    /// or block details were requested and no block has an unknown size.
+    pub fn decompressed_size(&self) -> Option<u64> {
+        if self.content_size.is_some() {
+            return self.content_size;
+        }
+        if self.blocks.len() != self.block_count {
+            return None;
+        }
+        let mut sum = 0;
+        for block in &self.blocks {
+            sum += u64::from(block.decompressed_size?);
+        }
+        Some(sum)
+    }
+}
+
+/// Like inspect() but for a buffer
+pub fn inspect_slice(source: &[u8], with_blocks: bool) -> Result<Vec<InspectedFrame>, String> {
+    inspect(&mut std::io::Cursor::new(source), with_blocks)
+}
+
+/// Walks all frames from the current position of source to its end without decompressing anything.
+/// Block contents are skipped with seek unless with_blocks is set, then the literals and sequences
+/// section headers of compressed blocks are parsed too. Offsets are relative to the starting position.
+pub fn inspect<R: Read + Seek>(
+    source: &mut R,
+    with_blocks: bool,
+) -> Result<Vec<InspectedFrame>, String> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let magic = match frame::read_frame_start(source) {
+            Ok(FrameStart::End) => break,
+            Ok(FrameStart::Frame { magic }) => magic,
+            Ok(FrameStart::Skippable {
+                magic_num,
+                data_size,
+            }) => {
+                skip(source, u64::from(data_size))
+                    .and_then(|_| check_not_past_end(source))
+                    .map_err(|m| format!("Skippable frame at offset {}: {}", offset, m))?;
+                frames.push(InspectedFrame::Skippable(SkippableFrameInfo {
+                    offset,
+                    magic_num,
+                    data_size,
+                }));
+                offset += 8 + u64::from(data_size);
+                continue;
+            }
+            Err(FrameStartError::Io(e)) => {
+                return Err(format!(
+                    "Frame at offset {}: Error while reading: {}",
+                    offset, e
+                ))
+            }
+            Err(FrameStartError::TrailingBytes(x)) => {
+                return Err(format!(
+                    "Frame at offset {}: Trailing {} bytes are too short to be a frame",
+                    offset, x
+                ))
+            }
+            Err(FrameStartError::TruncatedSkippableHeader) => {
+                return Err(format!(
+                    "Skippable frame at offset {} is missing its size",
+                    offset
+                ))
+            }
+        };
+
+        let info = inspect_frame(source, magic, offset, with_blocks)
+            .map_err(|m| format!("Frame at offset {}: {}", offset, m))?;
+        offset += info.compressed_size;
+        frames.push(InspectedFrame::Zstd(info));
+    }
+
+    Ok(frames)
+}
+
+fn inspect_frame<R: Read + Seek>(
+    source: &mut R,
+    magic: [u8; 4],
+    offset: u64,
+    with_blocks: bool,
+) -> Result<FrameInfo, String> {
+    let (frame, header_size) = frame::read_frame_header(&mut (&magic[..]).chain(&mut *source))?;
+    frame.check_valid()?;
+    let header = &frame.header;
+
+    let content_size = if header.descriptor.frame_content_size_bytes()? == 0 {
+        None
+    } else {
+        Some(header.frame_content_size()?)
+    };
+    let mut info = FrameInfo {
+        offset,
+        header_size,
+        compressed_size: u64::from(header_size),
+        window_size: header.window_size()?,
+        dict_id: header.dictiornary_id()?,
+        content_size,
+        single_segment: header.descriptor.single_segment_flag(),
+        checksum_flag: header.descriptor.content_checksum_flag(),
+        checksum: None,
+        block_count: 0,
+        blocks: Vec::new(),
+    };
+
+    let mut block_dec = block_decoder::new();
+    let mut content = Vec::new();
+    loop {
+        let (block_header, block_header_size) = block_dec.read_block_header(source)?;
+        let block_offset = offset + info.compressed_size;
+        info.compressed_size += u64::from(block_header_size) + u64::from(block_header.content_size);
+        info.block_count += 1;
+
+        if with_blocks {
+            let mut block = BlockInfo {
+                offset: block_offset,
+                block_type: block_header.block_type,
+                last_block: block_header.last_block,
+                compressed_size: block_header.content_size,
+                decompressed_size: Some(block_header.decompressed_size),
+                literals: None,
+                sequences: None,
+            };
+            if block_header.block_type == BlockType::Compressed {
+                content.resize(block_header.content_size as usize, 0);
+                source.read_exact(&mut content).map_err(|_| {
+                    format!("Error while reading the block at offset {}", block_offset)
+                })?;
+                inspect_compressed_block(&content, &mut block)
+                    .map_err(|m| format!("Block at offset {}: {}", block_offset, m))?;
+            } else {
+                skip(source, u64::from(block_header.content_size))?;
+            }
+            info.blocks.push(block);
+        } else {
+            skip(source, u64::from(block_header.content_size))?;
+        }
+
+        if block_header.last_block {
+            break;
+        }
+    }
+
+    if info.checksum_flag {
+        let mut checksum = [0u8; 4];
+        source
+            .read_exact(&mut checksum)
+            .map_err(|_| "Frame is missing its checksum".to_owned())?;
+        info.checksum = Some(crate::decoding::little_endian::read_little_endian_u32(
+            &checksum,
+        ));
+        info.compressed_size += 4;
+    }
+
+    check_not_past_end(source)?;
+    Ok(info)
+}
+
+fn inspect_compressed_block(raw: &[u8], block: &mut BlockInfo) -> Result<(), String> {
+    if raw.is_empty() {
+        return Err("Compressed block is empty".to_owned());
+    }
+    let mut literals = LiteralsSection::new();
+    let literals_header_size = literals.parse_from_header(raw)?;
+    let literals_size = match literals.compressed_size {
+        Some(x) => x,
+        None => match literals.ls_type {
+            LiteralsSectionType::RLE => 1,
+            _ => literals.regenerated_size,
+        },
+    } as usize;
+    let raw = &raw[literals_header_size as usize..];
+    if raw.len() < literals_size {
+        return Err(format!(
+            "Malformed section header. Says literals would be this long: {} but there are only {} bytes left",
+            literals_size,
+            raw.len()
+        ));
+    }
+
+    let mut sequences = SequencesHeader::new();
+    sequences.parse_from_header(&raw[literals_size..])?;
+
+    block.decompressed_size = if sequences.num_sequences == 0 {
+        Some(literals.regenerated_size)
+    } else {
+        None
+    };
+    block.literals = Some(LiteralsInfo {
+        ls_type: literals.ls_type,
+        regenerated_size: literals.regenerated_size,
+        compressed_size: literals.compressed_size,
+        num_streams: literals.num_streams,
+    });
+    block.sequences = Some(SequencesInfo {
+        num_sequences: sequences.num_sequences,
+        ll_mode: sequences.modes.map(|m| m.ll_mode()),
+        of_mode: sequences.modes.map(|m| m.of_mode()),
+        ml_mode: sequences.modes.map(|m| m.ml_mode()),
+    });
+    Ok(())
+}
+
+fn skip<R: Seek>(source: &mut R, amount: u64) -> Result<(), String> {
+    source
+        .seek(SeekFrom::Current(amount as i64))
+        .map(|_| ())
+        .map_err(|e| format!("Error while seeking: {}", e))
+}
+
+/// Seeking past the end is not an error, so truncated frames are only noticed by comparing with the end
+fn check_not_past_end<R: Seek>(source: &mut R) -> Result<(), String> {
+    let position = source
+        .stream_position()
+        .map_err(|e| format!("Error while getting the position of the source: {}", e))?;
+    let end = source
+        .seek(SeekFrom::End(0))
+        .map_err(|e| format!("Error while seeking to the end: {}", e))?;
+    if position > end {
+        return Err(format!(
+            "Source ends {} bytes before the end of the frame",
+            position - end
+        ));
+    }
+    source
+        .seek(SeekFrom::Start(position))
+        .map(|_| ())
+        .map_err(|e| format!("Error while seeking: {}", e))
+}
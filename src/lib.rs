@@ -1,14 +1,22 @@
 pub mod block;
+pub mod block_context;
+pub mod decoder_pool;
 pub mod decoding;
 pub mod errors;
 pub mod frame;
 pub mod frame_decoder;
 pub mod fse;
 pub mod huff0;
+pub mod inspect;
+pub mod parallel;
+pub mod salvage;
+pub mod seekable;
 pub mod streaming_decoder;
 mod tests;
+pub mod verify;
 
 pub const VERBOSE: bool = false;
 pub use frame_decoder::BlockDecodingStrategy;
 pub use frame_decoder::FrameDecoder;
 pub use streaming_decoder::StreamingDecoder;
+pub use verify::verify;
@@ -0,0 +1,249 @@
+use crate::decoding::block_decoder;
+use crate::frame::{self, FrameStart, FrameStartError};
+use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+
+/// Position of one zstd frame inside the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLocation {
+    /// Byte offset of the frame header in the input
+    pub offset: usize,
+    /// Size of the whole frame including header, blocks and checksum
+    pub size: usize,
+}
+
+/// Walks the frame and block headers in source and returns the location of every zstd frame.
+/// Skippable frames are stepped over and are not part of the result.
+pub fn find_frames(source: &[u8]) -> Result<Vec<FrameLocation>, String> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let rest = &source[offset..];
+        let mut reader = rest;
+        match frame::read_frame_start(&mut reader) {
+            Ok(FrameStart::End) => break,
+            Ok(FrameStart::Frame { .. }) => {}
+            Ok(FrameStart::Skippable { data_size, .. }) => {
+                let skip_size = data_size as usize;
+                if reader.len() < skip_size {
+                    return Err(format!(
+                        "Skippable frame at offset {} says it is {} bytes long but there are only {} bytes left",
+                        offset,
+                        skip_size,
+                        reader.len()
+                    ));
+                }
+                offset += 8 + skip_size;
+                continue;
+            }
+            Err(FrameStartError::TrailingBytes(x)) => {
+                return Err(format!(
+                    "Trailing {} bytes at offset {} are too short to be a frame",
+                    x, offset
+                ));
+            }
+            Err(FrameStartError::TruncatedSkippableHeader) => {
+                return Err(format!(
+                    "Skippable frame at offset {} is missing its size",
+                    offset
+                ));
+            }
+            Err(FrameStartError::Io(e)) => return Err(e.to_string()),
+        }
+
+        let size = frame_size(rest).map_err(|m| format!("Frame at offset {}: {}", offset, m))?;
+        frames.push(FrameLocation { offset, size });
+        offset += size;
+    }
+
+    Ok(frames)
+}
+
+/// Calculates the size of the frame at the start of source by reading the frame header and all block headers
+fn frame_size(source: &[u8]) -> Result<usize, String> {
+    let mut reader = source;
+    let (frame, header_size) = frame::read_frame_header(&mut reader)?;
+    frame.check_valid()?;
+
+    let mut size = header_size as usize;
+    let mut block_dec = block_decoder::new();
+    loop {
+        let (block_header, block_header_size) = block_dec.read_block_header(&mut reader)?;
+        let content_size = block_header.content_size as usize;
+        if reader.len() < content_size {
+            return Err(format!(
+                "Block needs {} bytes but there are only {} bytes left",
+                content_size,
+                reader.len()
+            ));
+        }
+        reader = &reader[content_size..];
+        size += block_header_size as usize + content_size;
+
+        if block_header.last_block {
+            break;
+        }
+    }
+
+    if frame.header.descriptor.content_checksum_flag() {
+        if reader.len() < 4 {
+            return Err("Frame is missing its checksum".to_owned());
+        }
+        size += 4;
+    }
+
+    Ok(size)
+}
+
+/// Decodes all frames in source on threads threads (0 means one per available cpu) and returns the concatenated output.
+/// This is meant for inputs that consist of many independent frames like the output of pzstd or zstd -T.
+/// Frame boundaries are found with find_frames() before any decoding happens.
+pub fn decode_frames(source: &[u8], threads: usize) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    decode_frames_to_writer(source, threads, &mut result)?;
+    Ok(result)
+}
+
+/// Decodes all frames in source on threads threads (0 means one per available cpu) and writes the output to target in order.
+///
+/// At most 2 * threads decoded frames are held in memory at a time. Workers that run ahead of the writer wait until
+/// the frames before them have been written. Returns how many bytes have been written to target.
+pub fn decode_frames_to_writer(
+    source: &[u8],
+    threads: usize,
+    target: &mut dyn std::io::Write,
+) -> Result<u64, String> {
+    let frames = find_frames(source)?;
+    let threads = if threads == 0 {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    } else {
+        threads
+    };
+    let threads = usize::min(threads, frames.len());
+    let max_in_flight = threads * 2;
+
+    let shared = Shared {
+        state: Mutex::new(WorkState {
+            next_frame: 0,
+            next_to_write: 0,
+            decoded: BTreeMap::new(),
+        }),
+        changed: Condvar::new(),
+        abort: AtomicBool::new(false),
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| decode_worker(source, &frames, max_in_flight, &shared));
+        }
+
+        let result = write_in_order(frames.len(), &shared, target);
+        if result.is_err() {
+            shared.abort.store(true, Ordering::SeqCst);
+            shared.changed.notify_all();
+        }
+        result
+    })
+}
+
+struct WorkState {
+    next_frame: usize,
+    next_to_write: usize,
+    decoded: BTreeMap<usize, Result<Vec<u8>, String>>,
+}
+
+struct Shared {
+    state: Mutex<WorkState>,
+    changed: Condvar,
+    abort: AtomicBool,
+}
+
+fn decode_worker(source: &[u8], frames: &[FrameLocation], max_in_flight: usize, shared: &Shared) {
+    let mut frame_dec = FrameDecoder::new();
+    loop {
+        let idx = {
+            let mut state = shared.state.lock().unwrap();
+            while state.next_frame < frames.len()
+                && state.next_frame >= state.next_to_write + max_in_flight
+                && !shared.abort.load(Ordering::SeqCst)
+            {
+                state = shared.changed.wait(state).unwrap();
+            }
+            if state.next_frame >= frames.len() || shared.abort.load(Ordering::SeqCst) {
+                return;
+            }
+            let idx = state.next_frame;
+            state.next_frame += 1;
+            idx
+        };
+
+        let location = frames[idx];
+        let frame_source = &source[location.offset..location.offset + location.size];
+        // a panic in a worker must not leave the writer waiting for this frame forever
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            decode_single_frame(&mut frame_dec, frame_source)
+        }))
+        .unwrap_or_else(|_| Err("Decoder panicked".to_owned()))
+        .map_err(|m| format!("Frame {} at offset {}: {}", idx, location.offset, m));
+        if result.is_err() {
+            // the decoder might be in an inconsistent state after a panic
+            frame_dec = FrameDecoder::new();
+        }
+
+        let mut state = shared.state.lock().unwrap();
+        state.decoded.insert(idx, result);
+        shared.changed.notify_all();
+    }
+}
+
+fn decode_single_frame(frame_dec: &mut FrameDecoder, mut source: &[u8]) -> Result<Vec<u8>, String> {
+    frame_dec.reset(&mut source)?;
+    frame_dec
+        .decode_blocks(&mut source, BlockDecodingStrategy::All)
+        .map_err(|e| e.to_string())?;
+    let result = frame_dec.collect().unwrap_or_default();
+
+    if let Some(chksum) = frame_dec.get_checksum_from_data() {
+        let calculated = frame_dec.get_calculated_checksum().unwrap_or(0);
+        if chksum != calculated {
+            return Err(format!(
+                "Checksum did not match! From data: {}, calculated while decoding: {}",
+                chksum, calculated
+            ));
+        }
+    }
+    Ok(result)
+}
+
+fn write_in_order(
+    num_frames: usize,
+    shared: &Shared,
+    target: &mut dyn std::io::Write,
+) -> Result<u64, String> {
+    let mut written = 0;
+    for idx in 0..num_frames {
+        let decoded = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if let Some(decoded) = state.decoded.remove(&idx) {
+                    state.next_to_write = idx + 1;
+                    shared.changed.notify_all();
+                    break decoded;
+                }
+                state = shared.changed.wait(state).unwrap();
+            }
+        };
+
+        let decoded = decoded?;
+        target
+            .write_all(&decoded)
+            .map_err(|e| format!("Error while writing decoded frame {}: {}", idx, e))?;
+        written += decoded.len() as u64;
+    }
+    Ok(written)
+}
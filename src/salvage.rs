@@ -0,0 +1,162 @@
+use crate::frame::{self, FrameStart, FrameStartError, MAGIC_NUM};
+use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+
+/// A place in the input where decoding failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corruption {
+    /// Byte offset in the input where the corruption begins. This is the start of the broken block,
+    /// or the start of the frame if its header is broken
+    pub offset: u64,
+    /// Counts all zstd frames that were attempted, including broken ones. Skippable frames are not counted,
+    /// a broken skippable frame gets the index the next zstd frame would have
+    pub frame_index: usize,
+    pub frame_offset: u64,
+    /// Index of the first block in the frame that could not be decoded. None if the frame header is broken
+    /// or if the frame decoded but its checksum did not match
+    pub block_index: Option<usize>,
+    pub message: String,
+}
+
+/// What salvage() could recover
+#[derive(Debug, Clone, Default)]
+pub struct SalvageResult {
+    /// Output of all completely decoded blocks of all frames, in order
+    pub data: Vec<u8>,
+    /// How many frames were decoded completely (their checksums, if present, matched)
+    pub frames_decoded: usize,
+    pub corruptions: Vec<Corruption>,
+}
+
+impl SalvageResult {
+    /// True if the whole input decoded without any problems
+    pub fn is_clean(&self) -> bool {
+        self.corruptions.is_empty()
+    }
+}
+
+/// Decodes as much as possible from a truncated or corrupted input.
+///
+/// Unlike the normal decoders this does not stop at the first error. The output of every block that
+/// could be decoded completely is kept. After a corruption the input is searched for the next frame
+/// (or skippable frame) magic number and decoding continues from there.
+pub fn salvage(source: &[u8]) -> SalvageResult {
+    salvage_with_decoder(source, &mut FrameDecoder::new())
+}
+
+/// Like salvage() but uses the provided decoder, e.g. one that has dicts added
+pub fn salvage_with_decoder(source: &[u8], frame_dec: &mut FrameDecoder) -> SalvageResult {
+    let mut result = SalvageResult::default();
+    let mut frame_index = 0;
+    let mut offset = 0;
+
+    while offset < source.len() {
+        let rest = &source[offset..];
+        let mut reader = rest;
+        match frame::read_frame_start(&mut reader) {
+            Ok(FrameStart::Skippable { data_size, .. }) if reader.len() >= data_size as usize => {
+                offset += 8 + data_size as usize;
+                continue;
+            }
+            Ok(FrameStart::Skippable { .. }) | Err(FrameStartError::TruncatedSkippableHeader) => {
+                // the frame is cut off, there can be nothing after it
+                result.corruptions.push(Corruption {
+                    offset: offset as u64,
+                    frame_index,
+                    frame_offset: offset as u64,
+                    block_index: None,
+                    message: "Skippable frame is cut off".to_owned(),
+                });
+                break;
+            }
+            // anything else is handed to the frame decoder, which reports what is wrong with it
+            _ => {}
+        }
+
+        let frame_offset = offset as u64;
+        let mut frame_source = rest;
+        if let Err(m) = frame_dec.reset(&mut frame_source) {
+            result.corruptions.push(Corruption {
+                offset: frame_offset,
+                frame_index,
+                frame_offset,
+                block_index: None,
+                message: m,
+            });
+            frame_index += 1;
+            match find_next_magic(source, offset + 1) {
+                Some(next) => offset = next,
+                None => break,
+            }
+            continue;
+        }
+
+        let mut failed = false;
+        loop {
+            let block_offset = frame_offset + frame_dec.bytes_read_from_source();
+            match frame_dec.decode_blocks(&mut frame_source, BlockDecodingStrategy::UptoBlocks(1)) {
+                Ok(finished) => {
+                    result.data.extend(frame_dec.collect().unwrap_or_default());
+                    if finished {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    result
+                        .data
+                        .extend(frame_dec.collect_all().unwrap_or_default());
+                    result.corruptions.push(Corruption {
+                        offset: block_offset,
+                        frame_index,
+                        frame_offset,
+                        block_index: Some(frame_dec.blocks_decoded()),
+                        message: e.to_string(),
+                    });
+                    match find_next_magic(source, block_offset as usize + 1) {
+                        Some(next) => offset = next,
+                        None => offset = source.len(),
+                    }
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        frame_index += 1;
+        if failed {
+            continue;
+        }
+
+        offset += frame_dec.bytes_read_from_source() as usize;
+        match frame_dec.get_checksum_from_data() {
+            Some(chksum) if Some(chksum) != frame_dec.get_calculated_checksum() => {
+                result.corruptions.push(Corruption {
+                    offset: frame_offset,
+                    frame_index: frame_index - 1,
+                    frame_offset,
+                    block_index: None,
+                    message: format!(
+                        "Checksum did not match! From data: {}, calculated while decoding: {}",
+                        chksum,
+                        frame_dec.get_calculated_checksum().unwrap_or(0)
+                    ),
+                });
+            }
+            _ => result.frames_decoded += 1,
+        }
+    }
+
+    result
+}
+
+/// Position of the next frame or skippable frame magic number at or after start
+fn find_next_magic(source: &[u8], start: usize) -> Option<usize> {
+    if start >= source.len() {
+        return None;
+    }
+    source[start..]
+        .windows(4)
+        .position(|w| {
+            let magic = crate::decoding::little_endian::read_little_endian_u32(w);
+            magic == MAGIC_NUM || frame::is_skippable(magic)
+        })
+        .map(|pos| start + pos)
+}
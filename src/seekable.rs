@@ -0,0 +1,504 @@
+use crate::frame::FrameHeader;
+use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom, Write};
+use twox_hash::XxHash64;
+
+/// Magic number of the skippable frame that holds the seek table
+pub const SEEK_TABLE_FRAME_MAGIC_NUM: u32 = 0x184D_2A5E;
+/// Magic number at the very end of a seekable file
+pub const SEEKABLE_MAGIC_NUM: u32 = 0x8F92_EAB1;
+/// Number_Of_Frames (4 bytes) + Seek_Table_Descriptor (1 byte) + Seekable_Magic_Number (4 bytes)
+pub const SEEK_TABLE_FOOTER_SIZE: usize = 9;
+/// Magic number (4 bytes) + Frame_Size (4 bytes) of the skippable frame
+pub const SKIPPABLE_HEADER_SIZE: usize = 8;
+
+const DEFAULT_CACHE_SIZE: usize = 4;
+
+/// One entry of the seek table, describing one independent frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeekTableEntry {
+    /// Where the frame starts in the compressed file
+    pub compressed_offset: u64,
+    /// Where the content of the frame starts in the decompressed stream
+    pub decompressed_offset: u64,
+    pub compressed_size: u32,
+    pub decompressed_size: u32,
+    /// Lower 32 bits of the XXH64 of the decompressed frame, if the table has checksums
+    pub checksum: Option<u32>,
+}
+
+/// The parsed seek table of a file in the zstd seekable format
+/// (see zstd/contrib/seekable_format/zstd_seekable_compression_format.md)
+#[derive(Debug, Clone)]
+pub struct SeekTable {
+    pub entries: Vec<SeekTableEntry>,
+    pub has_checksums: bool,
+}
+
+impl SeekTable {
+    /// Total size of the decompressed stream
+    pub fn decompressed_size(&self) -> u64 {
+        match self.entries.last() {
+            Some(e) => e.decompressed_offset + u64::from(e.decompressed_size),
+            None => 0,
+        }
+    }
+
+    /// Total size of all frames, not including the seek table itself
+    pub fn compressed_size(&self) -> u64 {
+        match self.entries.last() {
+            Some(e) => e.compressed_offset + u64::from(e.compressed_size),
+            None => 0,
+        }
+    }
+
+    /// Index of the frame that contains the byte at pos in the decompressed stream
+    pub fn frame_for_offset(&self, pos: u64) -> Option<usize> {
+        let idx = self
+            .entries
+            .partition_point(|e| e.decompressed_offset + u64::from(e.decompressed_size) <= pos);
+        if idx < self.entries.len() {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads the seek table from the end of source. The position of source after this call is unspecified.
+pub fn read_seek_table<R: Read + Seek>(source: &mut R) -> Result<SeekTable, String> {
+    let file_size = source
+        .seek(SeekFrom::End(0))
+        .map_err(|e| format!("Error while seeking to the end: {}", e))?;
+    if file_size < (SKIPPABLE_HEADER_SIZE + SEEK_TABLE_FOOTER_SIZE) as u64 {
+        return Err(format!(
+            "Source is too small to contain a seek table: {} bytes",
+            file_size
+        ));
+    }
+
+    let mut footer = [0u8; SEEK_TABLE_FOOTER_SIZE];
+    source
+        .seek(SeekFrom::End(-(SEEK_TABLE_FOOTER_SIZE as i64)))
+        .and_then(|_| source.read_exact(&mut footer))
+        .map_err(|e| format!("Error while reading the seek table footer: {}", e))?;
+
+    let num_frames = crate::decoding::little_endian::read_little_endian_u32(&footer[0..4]);
+    let descriptor = footer[4];
+    let magic = crate::decoding::little_endian::read_little_endian_u32(&footer[5..9]);
+    if magic != SEEKABLE_MAGIC_NUM {
+        return Err(format!(
+            "Seekable magic_num wrong. Is: {:#X}. Should be: {:#X}",
+            magic, SEEKABLE_MAGIC_NUM
+        ));
+    }
+    if descriptor & 0x7C != 0 {
+        return Err("Reserved bits in the seek table descriptor are set. Must be zero".to_owned());
+    }
+    let has_checksums = descriptor & 0x80 != 0;
+
+    let entry_size: u64 = if has_checksums { 12 } else { 8 };
+    let table_size = u64::from(num_frames) * entry_size + SEEK_TABLE_FOOTER_SIZE as u64;
+    if table_size + SKIPPABLE_HEADER_SIZE as u64 > file_size {
+        return Err(format!(
+            "Seek table with {} frames does not fit in a source of {} bytes",
+            num_frames, file_size
+        ));
+    }
+
+    let table_start = file_size - table_size - SKIPPABLE_HEADER_SIZE as u64;
+    let mut raw = vec![0u8; (table_size - SEEK_TABLE_FOOTER_SIZE as u64) as usize + 8];
+    source
+        .seek(SeekFrom::Start(table_start))
+        .and_then(|_| source.read_exact(&mut raw))
+        .map_err(|e| format!("Error while reading the seek table: {}", e))?;
+
+    let frame_magic = crate::decoding::little_endian::read_little_endian_u32(&raw[0..4]);
+    let frame_size = crate::decoding::little_endian::read_little_endian_u32(&raw[4..8]);
+    if frame_magic != SEEK_TABLE_FRAME_MAGIC_NUM {
+        return Err(format!(
+            "Seek table frame magic_num wrong. Is: {:#X}. Should be: {:#X}",
+            frame_magic, SEEK_TABLE_FRAME_MAGIC_NUM
+        ));
+    }
+    if u64::from(frame_size) != table_size {
+        return Err(format!(
+            "Seek table frame size is: {}, but the footer implies: {}",
+            frame_size, table_size
+        ));
+    }
+
+    let mut entries = Vec::with_capacity(num_frames as usize);
+    let mut compressed_offset = 0;
+    let mut decompressed_offset = 0;
+    for raw_entry in raw[8..].chunks_exact(entry_size as usize) {
+        let compressed_size =
+            crate::decoding::little_endian::read_little_endian_u32(&raw_entry[0..4]);
+        let decompressed_size =
+            crate::decoding::little_endian::read_little_endian_u32(&raw_entry[4..8]);
+        let checksum = if has_checksums {
+            Some(crate::decoding::little_endian::read_little_endian_u32(
+                &raw_entry[8..12],
+            ))
+        } else {
+            None
+        };
+
+        entries.push(SeekTableEntry {
+            compressed_offset,
+            decompressed_offset,
+            compressed_size,
+            decompressed_size,
+            checksum,
+        });
+        compressed_offset += u64::from(compressed_size);
+        decompressed_offset += u64::from(decompressed_size);
+    }
+
+    if compressed_offset != table_start {
+        return Err(format!(
+            "Frames in the seek table add up to {} bytes but the seek table starts at: {}",
+            compressed_offset, table_start
+        ));
+    }
+
+    Ok(SeekTable {
+        entries,
+        has_checksums,
+    })
+}
+
+/// Decoder for the zstd seekable format. It implements Read + Seek over the decompressed stream and
+/// only decodes the frames that are needed to serve a read. The last few decoded frames are cached.
+///
+/// ```no_run
+/// use ruzstd::seekable::SeekableDecoder;
+/// use std::io::{Read, Seek, SeekFrom};
+///
+/// let f = std::fs::File::open("log.zst").unwrap();
+/// let mut decoder = SeekableDecoder::new(f).unwrap();
+///
+/// // read the last kilobyte of the log without decoding the rest
+/// decoder.seek(SeekFrom::End(-1024)).unwrap();
+/// let mut tail = Vec::new();
+/// decoder.read_to_end(&mut tail).unwrap();
+/// ```
+pub struct SeekableDecoder<R: Read + Seek> {
+    source: R,
+    table: SeekTable,
+    frame_dec: FrameDecoder,
+    /// most recently used frame first
+    cache: VecDeque<(usize, Vec<u8>)>,
+    cache_size: usize,
+    position: u64,
+    compressed_buffer: Vec<u8>,
+}
+
+impl<R: Read + Seek> SeekableDecoder<R> {
+    /// Reads the seek table from the end of source
+    pub fn new(source: R) -> Result<SeekableDecoder<R>, String> {
+        Self::new_with_decoder(source, FrameDecoder::new())
+    }
+
+    /// Like new but uses the provided FrameDecoder (e.g. one that has dicts added already)
+    pub fn new_with_decoder(
+        mut source: R,
+        frame_dec: FrameDecoder,
+    ) -> Result<SeekableDecoder<R>, String> {
+        let table = read_seek_table(&mut source)?;
+        Ok(SeekableDecoder {
+            source,
+            table,
+            frame_dec,
+            cache: VecDeque::with_capacity(DEFAULT_CACHE_SIZE),
+            cache_size: DEFAULT_CACHE_SIZE,
+            position: 0,
+            compressed_buffer: Vec::new(),
+        })
+    }
+
+    /// How many decoded frames are kept in memory. Must be at least 1
+    pub fn set_cache_size(&mut self, frames: usize) {
+        self.cache_size = usize::max(frames, 1);
+        self.cache.truncate(self.cache_size);
+    }
+
+    pub fn seek_table(&self) -> &SeekTable {
+        &self.table
+    }
+
+    /// Total size of the decompressed stream
+    pub fn decompressed_size(&self) -> u64 {
+        self.table.decompressed_size()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Returns the decoded content of the frame, decoding it if it is not in the cache
+    pub fn decode_frame(&mut self, idx: usize) -> Result<&[u8], String> {
+        if let Some(cache_idx) = self.cache.iter().position(|(frame, _)| *frame == idx) {
+            let entry = self.cache.remove(cache_idx).unwrap();
+            self.cache.push_front(entry);
+            return Ok(&self.cache[0].1);
+        }
+
+        let entry = match self.table.entries.get(idx) {
+            Some(e) => *e,
+            None => {
+                return Err(format!(
+                    "Frame {} does not exist. There are only {} frames",
+                    idx,
+                    self.table.entries.len()
+                ))
+            }
+        };
+
+        self.compressed_buffer
+            .resize(entry.compressed_size as usize, 0);
+        self.source
+            .seek(SeekFrom::Start(entry.compressed_offset))
+            .and_then(|_| self.source.read_exact(&mut self.compressed_buffer))
+            .map_err(|e| format!("Error while reading frame {}: {}", idx, e))?;
+
+        let mut compressed = self.compressed_buffer.as_slice();
+        self.frame_dec.reset(&mut compressed)?;
+        self.frame_dec
+            .decode_blocks(&mut compressed, BlockDecodingStrategy::All)
+            .map_err(|e| format!("Error while decoding frame {}: {}", idx, e))?;
+        let decoded = self.frame_dec.collect().unwrap_or_default();
+
+        if self.frame_dec.bytes_read_from_source() != u64::from(entry.compressed_size) {
+            return Err(format!(
+                "Frame {} is {} bytes long but the seek table says: {}",
+                idx,
+                self.frame_dec.bytes_read_from_source(),
+                entry.compressed_size
+            ));
+        }
+        if decoded.len() != entry.decompressed_size as usize {
+            return Err(format!(
+                "Frame {} decoded to {} bytes but the seek table says: {}",
+                idx,
+                decoded.len(),
+                entry.decompressed_size
+            ));
+        }
+        if let Some(checksum) = entry.checksum {
+            let calculated = self.frame_dec.get_calculated_checksum().unwrap_or(0);
+            if calculated != checksum {
+                return Err(format!(
+                    "Checksum of frame {} did not match! From seek table: {}, calculated while decoding: {}",
+                    idx, checksum, calculated
+                ));
+            }
+        }
+
+        if self.cache.len() >= self.cache_size {
+            self.cache.pop_back();
+        }
+        self.cache.push_front((idx, decoded));
+        Ok(&self.cache[0].1)
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let idx = match self.table.frame_for_offset(self.position) {
+            Some(idx) => idx,
+            None => return Ok(0),
+        };
+        let frame_start = self.table.entries[idx].decompressed_offset;
+        let position = self.position;
+
+        let content = self.decode_frame(idx).map_err(std::io::Error::other)?;
+        let content = &content[(position - frame_start) as usize..];
+        let amount = usize::min(content.len(), buf.len());
+        buf[..amount].copy_from_slice(&content[..amount]);
+
+        self.position += amount as u64;
+        Ok(amount)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableDecoder<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(x) => Some(x),
+            SeekFrom::End(x) => self.decompressed_size().checked_add_signed(x),
+            SeekFrom::Current(x) => self.position.checked_add_signed(x),
+        };
+        match new_position {
+            Some(x) => {
+                self.position = x;
+                Ok(x)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Tried to seek before the start of the stream",
+            )),
+        }
+    }
+}
+
+/// Default for the max decompressed size of one frame written by the SeekableEncoder
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 1024 * 1024;
+/// Block_Maximum_Size from the zstd format
+const MAX_BLOCK_SIZE: usize = 128 * 1024;
+
+/// Writes the zstd seekable format. The input is cut into independent frames of at most max_frame_size decompressed bytes
+/// and finish() appends the seek table as a skippable frame.
+///
+/// ruzstd does not have a compressor. The frames only consist of raw and RLE blocks, so the output is barely smaller
+/// than the input. It is still valid zstd that any zstd decoder (and the seekable reader in zstd/contrib) can read.
+///
+/// ```
+/// use ruzstd::seekable::{SeekableDecoder, SeekableEncoder};
+/// use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+///
+/// let mut encoder = SeekableEncoder::new(Vec::new(), 4096);
+/// encoder.set_checksums(true);
+/// encoder.write_all(&vec![42u8; 10_000]).unwrap();
+/// let archive = encoder.finish().unwrap();
+///
+/// let mut decoder = SeekableDecoder::new(Cursor::new(archive)).unwrap();
+/// assert_eq!(decoder.seek_table().entries.len(), 3);
+/// decoder.seek(SeekFrom::Start(9_000)).unwrap();
+/// let mut rest = Vec::new();
+/// decoder.read_to_end(&mut rest).unwrap();
+/// assert_eq!(rest, vec![42u8; 1_000]);
+/// ```
+pub struct SeekableEncoder<W: Write> {
+    target: W,
+    max_frame_size: u32,
+    checksums: bool,
+    /// decompressed content of the frame that is not yet written
+    pending: Vec<u8>,
+    entries: Vec<SeekTableEntry>,
+    compressed_offset: u64,
+    decompressed_offset: u64,
+}
+
+impl<W: Write> SeekableEncoder<W> {
+    /// max_frame_size is the max amount of decompressed bytes per frame. Must be at least 1
+    pub fn new(target: W, max_frame_size: u32) -> SeekableEncoder<W> {
+        SeekableEncoder {
+            target,
+            max_frame_size: u32::max(max_frame_size, 1),
+            checksums: false,
+            pending: Vec::new(),
+            entries: Vec::new(),
+            compressed_offset: 0,
+            decompressed_offset: 0,
+        }
+    }
+
+    /// Store the lower 32 bits of the XXH64 of each frame in the seek table. Must be set before the first frame is written
+    pub fn set_checksums(&mut self, checksums: bool) {
+        self.checksums = checksums;
+    }
+
+    /// The frames written so far
+    pub fn entries(&self) -> &[SeekTableEntry] {
+        &self.entries
+    }
+
+    /// Write the pending data as a frame, even if it is smaller than max_frame_size. Does nothing if there is no pending data.
+    pub fn end_frame(&mut self) -> std::io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut frame = Vec::with_capacity(self.pending.len() + 32);
+        write_frame(&self.pending, &mut frame);
+        self.target.write_all(&frame)?;
+
+        let checksum = if self.checksums {
+            let mut hasher = XxHash64::with_seed(0);
+            hasher.write(&self.pending);
+            Some(hasher.finish() as u32)
+        } else {
+            None
+        };
+        self.entries.push(SeekTableEntry {
+            compressed_offset: self.compressed_offset,
+            decompressed_offset: self.decompressed_offset,
+            compressed_size: frame.len() as u32,
+            decompressed_size: self.pending.len() as u32,
+            checksum,
+        });
+        self.compressed_offset += frame.len() as u64;
+        self.decompressed_offset += self.pending.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Write the last frame and the seek table and return the target
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.end_frame()?;
+
+        let entry_size = if self.checksums { 12 } else { 8 };
+        let table_size = self.entries.len() * entry_size + SEEK_TABLE_FOOTER_SIZE;
+        let mut table = Vec::with_capacity(SKIPPABLE_HEADER_SIZE + table_size);
+        table.extend(SEEK_TABLE_FRAME_MAGIC_NUM.to_le_bytes());
+        table.extend((table_size as u32).to_le_bytes());
+        for entry in &self.entries {
+            table.extend(entry.compressed_size.to_le_bytes());
+            table.extend(entry.decompressed_size.to_le_bytes());
+            if self.checksums {
+                table.extend(entry.checksum.unwrap_or(0).to_le_bytes());
+            }
+        }
+        table.extend((self.entries.len() as u32).to_le_bytes());
+        table.push(if self.checksums { 0x80 } else { 0 });
+        table.extend(SEEKABLE_MAGIC_NUM.to_le_bytes());
+
+        self.target.write_all(&table)?;
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+impl<W: Write> Write for SeekableEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let space = self.max_frame_size as usize - self.pending.len();
+        let amount = usize::min(space, buf.len());
+        self.pending.extend_from_slice(&buf[..amount]);
+        if self.pending.len() == self.max_frame_size as usize {
+            self.end_frame()?;
+        }
+        Ok(amount)
+    }
+
+    /// Flushes the target. This does not end the current frame, use end_frame() for that
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.target.flush()
+    }
+}
+
+/// Writes content as a single segment frame made of raw and RLE blocks
+fn write_frame(content: &[u8], target: &mut Vec<u8>) {
+    // single segment, no checksum, no dict id. The window size is the frame content size
+    FrameHeader::builder()
+        .content_size(Some(content.len() as u64))
+        .build()
+        .and_then(|header| header.write_to(target))
+        .expect("Single segment frame headers always fit and writing to a Vec does not fail");
+
+    let mut blocks = content.chunks(MAX_BLOCK_SIZE).peekable();
+    while let Some(block) = blocks.next() {
+        let last_block = blocks.peek().is_none() as u32;
+        let is_rle = block.len() > 1 && block.iter().all(|b| *b == block[0]);
+        let block_type: u32 = if is_rle { 1 } else { 0 };
+        let header = last_block | (block_type << 1) | ((block.len() as u32) << 3);
+        target.extend(&header.to_le_bytes()[..3]);
+        if is_rle {
+            target.push(block[0]);
+        } else {
+            target.extend_from_slice(block);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::frame::FrameFormat;
 use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
 use std::io::Read;
 
@@ -12,20 +13,47 @@ pub struct StreamingDecoder<'a> {
 }
 
 impl<'a> StreamingDecoder<'a> {
-    pub fn new(source: &'a mut dyn Read) -> Result<StreamingDecoder, String> {
+    pub fn new(source: &'a mut dyn Read) -> Result<StreamingDecoder<'a>, String> {
         let mut decoder = FrameDecoder::new();
         decoder.init(source)?;
         Ok(StreamingDecoder { decoder, source })
     }
 
+    /// Like new but for frames in the given format, e.g. magicless frames
+    pub fn new_with_format(
+        source: &'a mut dyn Read,
+        format: FrameFormat,
+    ) -> Result<StreamingDecoder<'a>, String> {
+        let mut decoder = FrameDecoder::new();
+        decoder.set_frame_format(format);
+        Self::new_with_decoder(source, decoder)
+    }
+
     pub fn new_with_decoder(
         source: &'a mut dyn Read,
         mut decoder: FrameDecoder,
-    ) -> Result<StreamingDecoder, String> {
+    ) -> Result<StreamingDecoder<'a>, String> {
         decoder.init(source)?;
         Ok(StreamingDecoder { decoder, source })
     }
 
+    /// For a decoder that already read the frame header from source with init()/reset(). Unlike new_with_decoder this
+    /// can not fail, so the caller always gets the decoder back with inner()
+    pub fn from_initialized(
+        source: &'a mut dyn Read,
+        decoder: FrameDecoder,
+    ) -> StreamingDecoder<'a> {
+        StreamingDecoder { decoder, source }
+    }
+
+    /// See FrameDecoder::set_progress_callback. The callback is called while read() decodes more blocks
+    pub fn set_progress_callback(
+        &mut self,
+        callback: impl FnMut(&crate::frame_decoder::Progress) + Send + 'static,
+    ) {
+        self.decoder.set_progress_callback(callback);
+    }
+
     pub fn inner(self) -> FrameDecoder {
         self.decoder
     }
@@ -52,16 +80,12 @@ impl<'a> Read for StreamingDecoder<'a> {
             ) {
                 Ok(_) => { /*Nothing to do*/ }
                 Err(e) => {
-                    let err = std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Error in the zstd decoder: {:?}", e),
-                    );
-                    return Err(err);
+                    // the FrameDecoderError stays inside, callers can get it back with get_ref() and downcast_ref()
+                    return Err(std::io::Error::other(e));
                 }
             }
         }
 
-        let result = self.decoder.read(buf);
-        result
+        self.decoder.read(buf)
     }
 }
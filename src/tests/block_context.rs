@@ -0,0 +1,73 @@
+#[test]
+fn test_block_context_corpus_blocks() {
+    use crate::block::block::BlockType;
+    use crate::block_context::BlockContext;
+    use crate::inspect::{self, InspectedFrame};
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    let mut tested = 0;
+    for path in &files {
+        let compressed = fs::read(path).unwrap();
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        let frames = inspect::inspect_slice(&compressed, true).unwrap();
+        let info = match &frames[0] {
+            InspectedFrame::Zstd(info) => info,
+            InspectedFrame::Skippable(_) => panic!("Corpus files have no skippable frames"),
+        };
+        if info.dict_id.is_some() {
+            continue;
+        }
+
+        // decode the frame block by block as if the blocks were stored without the frame around them
+        let mut ctx = BlockContext::new(info.window_size as usize);
+        let mut result = Vec::new();
+        for block in &info.blocks {
+            let start = block.offset as usize + 3;
+            let content = &compressed[start..start + block.compressed_size as usize];
+            match block.block_type {
+                BlockType::Compressed => {
+                    let amount = ctx.decode_block(content, &mut result).unwrap();
+                    if let Some(size) = block.decompressed_size {
+                        assert_eq!(amount, size as usize);
+                    }
+                }
+                BlockType::Raw => {
+                    ctx.insert_block(content);
+                    result.extend_from_slice(content);
+                }
+                BlockType::RLE => {
+                    let data = vec![content[0]; block.decompressed_size.unwrap() as usize];
+                    ctx.insert_block(&data);
+                    result.extend(data);
+                }
+                BlockType::Reserved => panic!("Reserved block in corpus file"),
+            }
+        }
+        assert!(result == original, "{:?}", path);
+        tested += 1;
+    }
+    assert!(tested > 0);
+
+    let mut ctx = BlockContext::new(1024);
+    assert!(ctx
+        .decode_block(&vec![0; 200 * 1024], &mut Vec::new())
+        .is_err());
+    // raw literals "hello" and no sequences. The size field of a block header only has 21 bits, so with 2MiB of
+    // garbage after it the size would wrap around to just this block
+    let mut block = vec![5 << 3, b'h', b'e', b'l', b'l', b'o', 0];
+    let mut result = Vec::new();
+    assert_eq!(ctx.decode_block(&block, &mut result).unwrap(), 5);
+    assert_eq!(result, b"hello");
+    block.resize(2 * 1024 * 1024 + block.len(), 0);
+    assert!(ctx.decode_block(&block, &mut result).is_err());
+}
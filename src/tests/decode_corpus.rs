@@ -70,7 +70,10 @@ fn test_decode_corpus_files() {
         let mut original_p = p.clone();
         original_p.truncate(original_p.len() - 4);
         let original_f = fs::File::open(original_p).unwrap();
-        let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+        let original: Vec<u8> = std::io::BufReader::new(original_f)
+            .bytes()
+            .map(|x| x.unwrap())
+            .collect();
 
         println!("Results for file: {}", p.clone());
         let mut success = true;
@@ -85,7 +88,7 @@ fn test_decode_corpus_files() {
             fail_counter_size += 1;
         }
 
-        if frame_dec.bytes_read_from_source() != file_size as u64 {
+        if frame_dec.bytes_read_from_source() != file_size {
             println!(
                 "Framedecoder counted wrong amount of bytes: {}, should be: {}",
                 frame_dec.bytes_read_from_source(),
@@ -179,3 +182,163 @@ fn test_decode_corpus_files() {
 
     assert!(failed.is_empty());
 }
+
+#[test]
+fn test_decode_corpus_files_fused() {
+    use crate::frame_decoder;
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    let mut frame_dec = frame_decoder::FrameDecoder::new();
+    frame_dec.set_fused_sequence_execution(true);
+
+    let mut failed: Vec<String> = Vec::new();
+    for path in files {
+        let mut content = fs::File::open(&path).unwrap();
+        frame_dec.reset(&mut content).unwrap();
+        frame_dec
+            .decode_blocks(&mut content, frame_decoder::BlockDecodingStrategy::All)
+            .unwrap();
+        let result = frame_dec.collect().unwrap();
+
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        if original != result {
+            failed.push(path.to_str().unwrap().to_owned());
+        }
+    }
+
+    assert!(failed.is_empty(), "Failed files: {:?}", failed);
+}
+
+/// Without the simd feature every strategy copies like Scalar, so there is nothing to compare
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[test]
+fn test_decode_corpus_files_copy_strategies() {
+    use crate::decoding::simd_copy::CopyStrategy;
+    use crate::frame_decoder;
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    let mut scalar_dec = frame_decoder::FrameDecoder::new();
+    scalar_dec.set_copy_strategy(CopyStrategy::Scalar);
+
+    let mut failed: Vec<String> = Vec::new();
+    for strategy in &[CopyStrategy::Sse2, CopyStrategy::Avx2] {
+        assert_ne!(strategy.or_supported(), CopyStrategy::Scalar);
+        let mut frame_dec = frame_decoder::FrameDecoder::new();
+        frame_dec.set_copy_strategy(*strategy);
+
+        for path in &files {
+            let content = fs::read(path).unwrap();
+
+            let mut source = content.as_slice();
+            scalar_dec.reset(&mut source).unwrap();
+            scalar_dec
+                .decode_blocks(&mut source, frame_decoder::BlockDecodingStrategy::All)
+                .unwrap();
+            let scalar_result = scalar_dec.collect().unwrap();
+
+            let mut source = content.as_slice();
+            frame_dec.reset(&mut source).unwrap();
+            frame_dec
+                .decode_blocks(&mut source, frame_decoder::BlockDecodingStrategy::All)
+                .unwrap();
+            let result = frame_dec.collect().unwrap();
+
+            if scalar_result != result
+                || scalar_dec.get_calculated_checksum() != frame_dec.get_calculated_checksum()
+            {
+                failed.push(format!("{:?} with {:?}", path, strategy));
+            }
+        }
+    }
+
+    assert!(failed.is_empty(), "Differs from scalar: {:?}", failed);
+}
+
+#[test]
+fn test_decode_corpus_files_magicless() {
+    use crate::frame::{read_frame_header, FrameFormat};
+    use crate::frame_decoder;
+    use std::fs;
+    use std::io::Read;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    let mut frame_dec = frame_decoder::FrameDecoder::new();
+    frame_dec.set_frame_format(FrameFormat::Zstd1Magicless);
+
+    let mut failed: Vec<String> = Vec::new();
+    for path in &files {
+        let content = fs::read(path).unwrap();
+        let magicless = &content[4..];
+
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        let mut source = magicless;
+        frame_dec.reset(&mut source).unwrap();
+        frame_dec
+            .decode_blocks(&mut source, frame_decoder::BlockDecodingStrategy::All)
+            .unwrap();
+        if frame_dec.collect().unwrap() != original {
+            failed.push(path.to_str().unwrap().to_owned());
+        }
+
+        let mut source = magicless;
+        let mut stream = crate::streaming_decoder::StreamingDecoder::new_with_format(
+            &mut source,
+            FrameFormat::Zstd1Magicless,
+        )
+        .unwrap();
+        let mut result = Vec::new();
+        stream.read_to_end(&mut result).unwrap();
+        if result != original {
+            failed.push(path.to_str().unwrap().to_owned());
+        }
+
+        // writing the header without magic gives back the stripped bytes
+        let (frame, header_size) = read_frame_header(&mut content.as_slice()).unwrap();
+        let mut header = Vec::new();
+        let written = frame
+            .header
+            .write_to_with_format(&mut header, FrameFormat::Zstd1Magicless)
+            .unwrap();
+        assert_eq!(written, header_size - 4);
+        assert_eq!(header, &magicless[..written as usize]);
+    }
+
+    assert!(failed.is_empty(), "Failed files: {:?}", failed);
+
+    // a normal frame is not a valid magicless frame
+    let content = fs::read(&files[0]).unwrap();
+    let mut source = content.as_slice();
+    let result = frame_dec.reset(&mut source).and_then(|_| {
+        frame_dec
+            .decode_blocks(&mut source, frame_decoder::BlockDecodingStrategy::All)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    });
+    assert!(result.is_err());
+}
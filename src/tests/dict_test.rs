@@ -91,7 +91,10 @@ fn test_dict_decoding() {
 
     let mut files: Vec<_> = fs::read_dir("./dict_tests/files").unwrap().collect();
     let dict = fs::File::open("./dict_tests/dictionary").unwrap();
-    let dict: Vec<u8> = dict.bytes().map(|x| x.unwrap()).collect();
+    let dict: Vec<u8> = std::io::BufReader::new(dict)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
     files.sort_by_key(|x| match x {
         Err(_) => "".to_owned(),
@@ -142,7 +145,10 @@ fn test_dict_decoding() {
         let mut original_p = p.clone();
         original_p.truncate(original_p.len() - 4);
         let original_f = fs::File::open(original_p).unwrap();
-        let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+        let original: Vec<u8> = std::io::BufReader::new(original_f)
+            .bytes()
+            .map(|x| x.unwrap())
+            .collect();
 
         println!("Results for file: {}", p.clone());
         let mut success = true;
@@ -157,7 +163,7 @@ fn test_dict_decoding() {
             fail_counter_size += 1;
         }
 
-        if frame_dec.bytes_read_from_source() != file_size as u64 {
+        if frame_dec.bytes_read_from_source() != file_size {
             println!(
                 "Framedecoder counted wrong amount of bytes: {}, should be: {}",
                 frame_dec.bytes_read_from_source(),
@@ -250,3 +256,161 @@ fn test_dict_decoding() {
 
     assert!(failed.is_empty());
 }
+
+#[test]
+fn test_decoder_pool_shared_dict() {
+    use crate::decoder_pool::DecoderPool;
+    use crate::decoding::dictionary::Dictionary;
+    use crate::frame_decoder::BlockDecodingStrategy;
+    use std::fs;
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Dictionary>();
+    assert_send_sync::<DecoderPool>();
+
+    let dict = fs::read("./dict_tests/dictionary").unwrap();
+    let dict = Arc::new(Dictionary::decode_dict(&dict).unwrap());
+
+    let mut files: Vec<_> = fs::read_dir("./dict_tests/files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    let mut pool = DecoderPool::new(4);
+    pool.add_shared_dict(dict);
+
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let pool = &pool;
+            let files = &files;
+            scope.spawn(move || {
+                for path in files.iter().skip(thread).step_by(4) {
+                    let content = fs::read(path).unwrap();
+                    let mut source = content.as_slice();
+
+                    let mut frame_dec = pool.get();
+                    frame_dec.reset(&mut source).unwrap();
+                    frame_dec
+                        .decode_blocks(&mut source, BlockDecodingStrategy::All)
+                        .unwrap();
+                    let result = frame_dec.collect().unwrap();
+
+                    let mut original_p = path.to_str().unwrap().to_owned();
+                    original_p.truncate(original_p.len() - 4);
+                    let original = fs::read(original_p).unwrap();
+                    assert!(result == original, "Wrong result for {:?}", path);
+                }
+            });
+        }
+    });
+
+    assert!(pool.idle_decoders() > 0);
+    assert!(pool.idle_decoders() <= 4);
+}
+
+#[test]
+fn test_dict_provider() {
+    use crate::decoding::dictionary::Dictionary;
+    use crate::errors::FrameDecoderError;
+    use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let dict = fs::read("./dict_tests/dictionary").unwrap();
+    let dict = Arc::new(Dictionary::decode_dict(&dict).unwrap());
+    let dict_id = dict.id;
+
+    let mut files: Vec<_> = fs::read_dir("./dict_tests/files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(10);
+
+    // without a dict the error names the missing id
+    let content = fs::read(&files[0]).unwrap();
+    let mut source = content.as_slice();
+    let mut frame_dec = FrameDecoder::new();
+    frame_dec.reset(&mut source).unwrap();
+    match frame_dec.decode_blocks(&mut source, BlockDecodingStrategy::All) {
+        Err(FrameDecoderError::DictNotProvided { dict_id: id }) => assert_eq!(id, dict_id),
+        _ => panic!("Expected DictNotProvided"),
+    }
+
+    // the provider is only asked for dicts when a frame needs them
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let provider_lookups = Arc::clone(&lookups);
+    let provided = Arc::clone(&dict);
+    frame_dec.set_dict_provider(Arc::new(move |id: u32| {
+        provider_lookups.fetch_add(1, Ordering::SeqCst);
+        if id == provided.id {
+            Some(Arc::clone(&provided))
+        } else {
+            None
+        }
+    }));
+    assert_eq!(lookups.load(Ordering::SeqCst), 0);
+
+    for path in &files {
+        let content = fs::read(path).unwrap();
+        let mut source = content.as_slice();
+        frame_dec.reset(&mut source).unwrap();
+        frame_dec
+            .decode_blocks(&mut source, BlockDecodingStrategy::All)
+            .unwrap();
+        let result = frame_dec.collect().unwrap();
+
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+        assert!(result == original, "Wrong result for {:?}", path);
+    }
+    assert_eq!(lookups.load(Ordering::SeqCst), files.len());
+}
+
+#[test]
+fn test_decoder_pool_resets_settings() {
+    use crate::decoder_pool::DecoderPool;
+    use crate::frame::FrameFormat;
+    use crate::frame_decoder::BlockDecodingStrategy;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let content = fs::read("./decodecorpus_files/z000033.zst").unwrap();
+    let original = fs::read("./decodecorpus_files/z000033").unwrap();
+    let pool = DecoderPool::new(1);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    {
+        let mut frame_dec = pool.get();
+        let counter = Arc::clone(&calls);
+        frame_dec.set_progress_callback(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        frame_dec.set_frame_format(FrameFormat::Zstd1Magicless);
+        frame_dec.set_max_window_size(1024);
+        frame_dec.set_block_timings(true);
+        frame_dec.set_prefix(vec![1u8; 100]);
+        frame_dec
+            .add_dict(&fs::read("./dict_tests/dictionary").unwrap())
+            .unwrap();
+    }
+    assert_eq!(pool.idle_decoders(), 1);
+
+    // the same decoder again, it must decode a normal frame with a big window like a fresh one
+    let mut frame_dec = pool.get();
+    let mut source = content.as_slice();
+    frame_dec.reset(&mut source).unwrap();
+    frame_dec
+        .decode_blocks(&mut source, BlockDecodingStrategy::All)
+        .unwrap();
+    assert!(frame_dec.collect().unwrap() == original);
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+    assert!(frame_dec.block_timings().is_none());
+}
@@ -0,0 +1,162 @@
+#[test]
+fn test_frame_header_roundtrip() {
+    use crate::frame::{read_frame_header, FrameHeader};
+
+    let window_sizes = [
+        None,
+        Some(1),
+        Some(1024),
+        Some(1025),
+        Some(1 << 20),
+        Some(3 << 27),
+    ];
+    let content_sizes = [
+        None,
+        Some(0),
+        Some(255),
+        Some(256),
+        Some(65791),
+        Some(65792),
+        Some(1 << 33),
+    ];
+    let dict_ids = [None, Some(0), Some(1), Some(255), Some(256), Some(70000)];
+
+    for window_size in window_sizes {
+        for content_size in content_sizes {
+            for dict_id in dict_ids {
+                for checksum in [false, true] {
+                    let builder = FrameHeader::builder()
+                        .window_size(window_size)
+                        .content_size(content_size)
+                        .dict_id(dict_id)
+                        .content_checksum(checksum);
+                    if window_size.is_none() && content_size.is_none() {
+                        assert!(builder.build().is_err());
+                        continue;
+                    }
+                    let header = builder.build().unwrap();
+
+                    let mut raw = Vec::new();
+                    let written = header.write_to(&mut raw).unwrap();
+                    assert_eq!(written as usize, raw.len());
+
+                    let (frame, read) = read_frame_header(&mut raw.as_slice()).unwrap();
+                    frame.check_valid().unwrap();
+                    assert_eq!(read, written);
+                    let decoded = &frame.header;
+                    assert_eq!(decoded.descriptor.content_checksum_flag(), checksum);
+                    assert_eq!(
+                        decoded.dictiornary_id().unwrap(),
+                        dict_id.filter(|x| *x != 0)
+                    );
+                    if let Some(content_size) = content_size {
+                        assert_eq!(decoded.frame_content_size().unwrap(), content_size);
+                    }
+                    match window_size {
+                        Some(x) => {
+                            let decoded_window = decoded.window_size().unwrap();
+                            assert!(decoded_window >= x);
+                            assert!(decoded_window <= u64::max(x, 1024) * 9 / 8);
+                        }
+                        None => assert!(decoded.descriptor.single_segment_flag()),
+                    }
+
+                    // writing the read header again gives the same bytes
+                    let mut rewritten = Vec::new();
+                    decoded
+                        .to_builder()
+                        .unwrap()
+                        .build()
+                        .unwrap()
+                        .write_to(&mut rewritten)
+                        .unwrap();
+                    assert_eq!(raw, rewritten);
+                }
+            }
+        }
+    }
+
+    assert!(FrameHeader::builder()
+        .window_size(Some(1 << 45))
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_frame_header_rewrite_corpus() {
+    use crate::frame::read_frame_header;
+    use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(20);
+
+    for path in &files {
+        let compressed = fs::read(path).unwrap();
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        // add the content size to the header and check that the frame still decodes
+        let (frame, header_size) = read_frame_header(&mut compressed.as_slice()).unwrap();
+        let builder = frame
+            .header
+            .to_builder()
+            .unwrap()
+            .content_size(Some(original.len() as u64));
+        let mut rewritten = Vec::new();
+        builder.build().unwrap().write_to(&mut rewritten).unwrap();
+        rewritten.extend(&compressed[header_size as usize..]);
+
+        let mut source = rewritten.as_slice();
+        let mut frame_dec = FrameDecoder::new();
+        frame_dec.reset(&mut source).unwrap();
+        assert_eq!(frame_dec.content_size(), Some(original.len() as u64));
+        frame_dec
+            .decode_blocks(&mut source, BlockDecodingStrategy::All)
+            .unwrap();
+        assert!(frame_dec.collect().unwrap() == original, "{:?}", path);
+    }
+}
+
+#[test]
+fn test_read_frame_start() {
+    use crate::frame::{read_frame_start, skip_skippable_data, FrameStart, FrameStartError};
+
+    let mut raw = vec![0x53, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3];
+    raw.extend(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]);
+    let mut source = raw.as_slice();
+
+    assert_eq!(
+        read_frame_start(&mut source).unwrap(),
+        FrameStart::Skippable {
+            magic_num: 0x184D_2A53,
+            data_size: 3
+        }
+    );
+    assert_eq!(skip_skippable_data(&mut source, 3).unwrap(), 3);
+    assert_eq!(
+        read_frame_start(&mut source).unwrap(),
+        FrameStart::Frame {
+            magic: [0x28, 0xB5, 0x2F, 0xFD]
+        }
+    );
+    source = &source[1..];
+    assert_eq!(read_frame_start(&mut source).unwrap(), FrameStart::End);
+
+    let mut source = &raw[..6];
+    assert!(matches!(
+        read_frame_start(&mut source),
+        Err(FrameStartError::TruncatedSkippableHeader)
+    ));
+    let mut source = &raw[..2];
+    assert!(matches!(
+        read_frame_start(&mut source),
+        Err(FrameStartError::TrailingBytes(2))
+    ));
+}
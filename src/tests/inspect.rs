@@ -0,0 +1,84 @@
+#[test]
+fn test_inspect_corpus_files() {
+    use crate::block::block::BlockType;
+    use crate::inspect::{self, InspectedFrame};
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    for path in &files {
+        let compressed = fs::read(path).unwrap();
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        let frames = inspect::inspect_slice(&compressed, true).unwrap();
+        assert_eq!(frames.len(), 1, "{:?}", path);
+        let info = match &frames[0] {
+            InspectedFrame::Zstd(info) => info,
+            InspectedFrame::Skippable(_) => panic!("Corpus files have no skippable frames"),
+        };
+        assert_eq!(info.offset, 0);
+        assert_eq!(info.compressed_size, compressed.len() as u64, "{:?}", path);
+        assert_eq!(info.block_count, info.blocks.len());
+        assert!(info.blocks.last().unwrap().last_block);
+
+        let blocks_size: u64 = info
+            .blocks
+            .iter()
+            .map(|b| 3 + u64::from(b.compressed_size))
+            .sum();
+        let checksum_size = if info.checksum_flag { 4 } else { 0 };
+        assert_eq!(
+            u64::from(info.header_size) + blocks_size + checksum_size,
+            info.compressed_size
+        );
+        for block in &info.blocks {
+            assert_eq!(
+                block.literals.is_some(),
+                block.block_type == BlockType::Compressed
+            );
+        }
+        if let Some(size) = info.decompressed_size() {
+            assert_eq!(size, original.len() as u64, "{:?}", path);
+        }
+
+        // without block details the frame level info is the same
+        match &inspect::inspect_slice(&compressed, false).unwrap()[0] {
+            InspectedFrame::Zstd(summary) => {
+                assert_eq!(summary.compressed_size, info.compressed_size);
+                assert_eq!(summary.block_count, info.block_count);
+                assert_eq!(summary.checksum, info.checksum);
+                assert!(summary.blocks.is_empty());
+            }
+            InspectedFrame::Skippable(_) => panic!("Corpus files have no skippable frames"),
+        }
+
+        assert!(inspect::inspect_slice(&compressed[..compressed.len() - 1], false).is_err());
+    }
+
+    // frames and skippable frames in a row
+    let mut source = fs::read(&files[0]).unwrap();
+    let first_size = source.len() as u64;
+    source.extend(&[0x53, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+    source.extend(fs::read(&files[1]).unwrap());
+    let frames = inspect::inspect_slice(&source, false).unwrap();
+    assert_eq!(frames.len(), 3);
+    match &frames[1] {
+        InspectedFrame::Skippable(skippable) => {
+            assert_eq!(skippable.offset, first_size);
+            assert_eq!(skippable.magic_num, 0x184D_2A53);
+            assert_eq!(skippable.data_size, 3);
+        }
+        InspectedFrame::Zstd(_) => panic!("Second frame should be skippable"),
+    }
+    match &frames[2] {
+        InspectedFrame::Zstd(info) => assert_eq!(info.offset, first_size + 11),
+        InspectedFrame::Skippable(_) => panic!("Third frame should be a zstd frame"),
+    }
+}
@@ -57,10 +57,12 @@ fn test_decode_from_to() {
     let f = File::open("./decodecorpus_files/z000088.zst").unwrap();
     let mut frame_dec = frame_decoder::FrameDecoder::new();
 
-    let content: Vec<u8> = f.bytes().map(|x| x.unwrap()).collect();
+    let content: Vec<u8> = std::io::BufReader::new(f)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
-    let mut target = Vec::with_capacity(1024 * 1024);
-    target.resize(1024 * 1024, 0u8);
+    let mut target = vec![0; 1024 * 1024];
 
     // first part
     let source1 = &content[..50 * 1024];
@@ -93,11 +95,11 @@ fn test_decode_from_to() {
     let result = &target.as_slice()[..written];
 
     if read != content.len() {
-        panic!(format!(
+        panic!(
             "Byte counter: {} was wrong. Should be: {}",
             read,
             content.len()
-        ));
+        );
     }
 
     match frame_dec.get_checksum_from_data() {
@@ -116,7 +118,10 @@ fn test_decode_from_to() {
     }
 
     let original_f = File::open("./decodecorpus_files/z000088").unwrap();
-    let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+    let original: Vec<u8> = std::io::BufReader::new(original_f)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
     if original.len() != result.len() {
         panic!(
@@ -174,7 +179,10 @@ fn test_specific_file() {
     let result = frame_dec.collect().unwrap();
 
     let original_f = fs::File::open("./decodecorpus_files/z000088").unwrap();
-    let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+    let original: Vec<u8> = std::io::BufReader::new(original_f)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
     println!("Results for file: {}", path);
 
@@ -218,7 +226,10 @@ fn test_streaming() {
     Read::read_to_end(&mut stream, &mut result).unwrap();
 
     let original_f = fs::File::open("./decodecorpus_files/z000088").unwrap();
-    let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+    let original: Vec<u8> = std::io::BufReader::new(original_f)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
     if original.len() != result.len() {
         panic!(
@@ -258,7 +269,10 @@ fn test_streaming() {
     Read::read_to_end(&mut stream, &mut result).unwrap();
 
     let original_f = fs::File::open("./decodecorpus_files/z000068").unwrap();
-    let original: Vec<u8> = original_f.bytes().map(|x| x.unwrap()).collect();
+    let original: Vec<u8> = std::io::BufReader::new(original_f)
+        .bytes()
+        .map(|x| x.unwrap())
+        .collect();
 
     println!("Results for file:");
 
@@ -291,6 +305,56 @@ fn test_streaming() {
 }
 
 pub mod bit_reader;
+pub mod block_context;
 pub mod decode_corpus;
 pub mod dict_test;
+pub mod frame_header;
 pub mod fuzz_regressions;
+pub mod inspect;
+pub mod parallel;
+pub mod patch;
+pub mod progress;
+pub mod salvage;
+pub mod seekable;
+pub mod timings;
+pub mod verify;
+
+#[test]
+fn test_repeat_overlapping() {
+    use crate::decoding::decodebuffer::Decodebuffer;
+    use crate::decoding::simd_copy::CopyStrategy;
+
+    // without the simd feature Sse2 and Avx2 copy like Scalar and would only test the scalar copy again
+    let strategies: &[CopyStrategy] = if cfg!(all(feature = "simd", target_arch = "x86_64")) {
+        &[CopyStrategy::Scalar, CopyStrategy::Sse2, CopyStrategy::Avx2]
+    } else {
+        &[CopyStrategy::Scalar]
+    };
+
+    // compare against a byte by byte copy for all small offsets and a range of match lengths
+    for strategy in strategies {
+        for offset in 1..70 {
+            for match_length in 0..150 {
+                let mut buf = Decodebuffer::new(1024);
+                buf.set_copy_strategy(*strategy);
+                let start: Vec<u8> = (0..80).collect();
+                buf.push(&start);
+                buf.repeat(offset, match_length).unwrap();
+                // literals after the match must overwrite whatever the match copy wrote past its end
+                buf.push(&start[..offset.min(start.len())]);
+
+                let mut expected = start.clone();
+                for _ in 0..match_length {
+                    expected.push(expected[expected.len() - offset]);
+                }
+                expected.extend(&start[..offset.min(start.len())]);
+
+                assert_eq!(
+                    buf.buffer, expected,
+                    "strategy: {:?}, offset: {}, match_length: {}",
+                    strategy, offset, match_length
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+#[test]
+fn test_parallel_decode_multiple_frames() {
+    use crate::parallel;
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+
+    // concatenate all corpus files into one multi-frame input and put a skippable frame in between
+    let mut source = Vec::new();
+    let mut expected = Vec::new();
+    let mut num_frames = 0;
+    for (idx, path) in files.iter().enumerate() {
+        source.extend(fs::read(path).unwrap());
+        num_frames += 1;
+        if idx == files.len() / 2 {
+            source.extend(&[0x50, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+        }
+
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        expected.extend(fs::read(original_p).unwrap());
+    }
+
+    let frames = parallel::find_frames(&source).unwrap();
+    assert_eq!(frames.len(), num_frames);
+    assert_eq!(frames[0].offset, 0);
+
+    for threads in &[1, 3, 8] {
+        let result = parallel::decode_frames(&source, *threads).unwrap();
+        assert!(
+            result == expected,
+            "Output differs with {} threads",
+            threads
+        );
+    }
+
+    // truncated input must be reported as an error and not panic or hang
+    let truncated = &source[..source.len() - 10];
+    assert!(parallel::decode_frames(truncated, 4).is_err());
+}
@@ -0,0 +1,45 @@
+#[test]
+fn test_patch_from() {
+    use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+    use std::fs;
+    use std::sync::Arc;
+
+    // made with: zstd --patch-from=decodecorpus_files/z000055 new -o patch_tests/z000055.patch.zst
+    let reference = fs::read("./decodecorpus_files/z000055").unwrap();
+    let mut expected = reference[..5000].to_vec();
+    expected.extend_from_slice(b"ruzstd patch test");
+    expected.extend_from_slice(&reference[6000..]);
+    expected.extend_from_slice(&reference[..3000]);
+    let patch = fs::read("./patch_tests/z000055.patch.zst").unwrap();
+
+    let mut frame_dec = FrameDecoder::new();
+    frame_dec.set_max_window_size(1024);
+    assert!(frame_dec.reset(&mut patch.as_slice()).is_err());
+
+    // the window is raised to cover the reference
+    let reference: Arc<[u8]> = reference.into();
+    frame_dec.set_prefix(Arc::clone(&reference));
+    for _ in 0..2 {
+        let mut source = patch.as_slice();
+        frame_dec.reset(&mut source).unwrap();
+        frame_dec
+            .decode_blocks(&mut source, BlockDecodingStrategy::All)
+            .unwrap();
+        let decoded = frame_dec.collect().unwrap();
+        assert_eq!(
+            frame_dec.get_checksum_from_data(),
+            frame_dec.get_calculated_checksum()
+        );
+        assert!(decoded == expected, "patch was not applied correctly");
+        // the frame shares the prefix with the decoder instead of copying it
+        assert_eq!(Arc::strong_count(&reference), 3);
+    }
+
+    frame_dec.clear_prefix();
+    frame_dec.set_max_window_size(crate::frame_decoder::MAX_WINDOW_SIZE);
+    let mut source = patch.as_slice();
+    frame_dec.reset(&mut source).unwrap();
+    assert!(frame_dec
+        .decode_blocks(&mut source, BlockDecodingStrategy::All)
+        .is_err());
+}
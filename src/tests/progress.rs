@@ -0,0 +1,56 @@
+#[test]
+fn test_progress_callback() {
+    use crate::frame_decoder::{FrameDecoder, Progress};
+    use crate::streaming_decoder::StreamingDecoder;
+    use std::fs;
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    let compressed = fs::read("./decodecorpus_files/z000033.zst").unwrap();
+    let original = fs::read("./decodecorpus_files/z000033").unwrap();
+
+    let check = |calls: &[Progress]| {
+        assert!(calls.len() > 1);
+        for (pair, idx) in calls.windows(2).zip(1..) {
+            assert_eq!(pair[0].blocks_decoded, idx);
+            assert!(pair[0].bytes_read < pair[1].bytes_read);
+            assert!(pair[0].bytes_decoded <= pair[1].bytes_decoded);
+        }
+        let last = calls.last().unwrap();
+        assert_eq!(last.bytes_read, compressed.len() as u64);
+        assert_eq!(last.bytes_decoded, original.len() as u64);
+        if let Some(size) = last.content_size {
+            assert_eq!(size, original.len() as u64);
+        }
+    };
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&calls);
+    let mut source = compressed.as_slice();
+    let mut decoder = StreamingDecoder::new(&mut source).unwrap();
+    decoder.set_progress_callback(move |p| recorded.lock().unwrap().push(*p));
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    assert!(result == original);
+    check(&calls.lock().unwrap());
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&calls);
+    let mut frame_dec = FrameDecoder::new();
+    frame_dec.set_progress_callback(move |p| recorded.lock().unwrap().push(*p));
+    let mut target = vec![0; original.len()];
+    let mut source = compressed.as_slice();
+    let mut written = 0;
+    loop {
+        let (read, w) = frame_dec
+            .decode_from_to(source, &mut target[written..])
+            .unwrap();
+        source = &source[read..];
+        written += w;
+        if frame_dec.is_finished() && frame_dec.can_collect() == 0 {
+            break;
+        }
+    }
+    assert!(target == original);
+    check(&calls.lock().unwrap());
+}
@@ -0,0 +1,61 @@
+#[test]
+fn test_salvage_damaged_input() {
+    use crate::inspect::{self, InspectedFrame};
+    use crate::salvage::salvage;
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(30);
+
+    let mut source = Vec::new();
+    let mut outputs = Vec::new();
+    let mut offsets = Vec::new();
+    for path in &files {
+        offsets.push(source.len());
+        source.extend(fs::read(path).unwrap());
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        outputs.push(fs::read(original_p).unwrap());
+    }
+    let expected: Vec<u8> = outputs.concat();
+
+    let result = salvage(&source);
+    assert!(result.is_clean(), "{:?}", result.corruptions);
+    assert_eq!(result.frames_decoded, files.len());
+    assert!(result.data == expected);
+
+    // break the first block of the second frame by making it a reserved block
+    let frames = inspect::inspect_slice(&source, true).unwrap();
+    let first_block = match &frames[1] {
+        InspectedFrame::Zstd(info) => info.blocks[0].offset as usize,
+        InspectedFrame::Skippable(_) => panic!("Corpus files have no skippable frames"),
+    };
+    let mut broken = source.clone();
+    broken[first_block] |= 0b110;
+    let result = salvage(&broken);
+    assert_eq!(result.corruptions.len(), 1, "{:?}", result.corruptions);
+    let corruption = &result.corruptions[0];
+    assert_eq!(corruption.offset, first_block as u64);
+    assert_eq!(corruption.frame_index, 1);
+    assert_eq!(corruption.frame_offset, offsets[1] as u64);
+    assert_eq!(corruption.block_index, Some(0));
+    assert_eq!(result.frames_decoded, files.len() - 1);
+    let mut without_second = outputs[0].clone();
+    without_second.extend(outputs[2..].concat());
+    assert!(result.data == without_second);
+
+    // cut off in the middle of the last frame, everything before the broken block is recovered
+    let last = offsets[files.len() - 1];
+    let cut = last + (source.len() - last) / 2;
+    let result = salvage(&source[..cut]);
+    assert_eq!(result.corruptions.len(), 1, "{:?}", result.corruptions);
+    assert_eq!(result.corruptions[0].frame_index, files.len() - 1);
+    assert_eq!(result.frames_decoded, files.len() - 1);
+    assert!(result.data.len() >= expected.len() - outputs[files.len() - 1].len());
+    assert!(result.data[..] == expected[..result.data.len()]);
+}
@@ -0,0 +1,247 @@
+#[test]
+fn test_seekable_decoder() {
+    use crate::seekable::SeekableDecoder;
+    use std::fs;
+    use std::hash::Hasher;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(20);
+
+    // build a seekable file by hand: the corpus frames followed by a seek table with checksums
+    let mut source = Vec::new();
+    let mut expected = Vec::new();
+    let mut table = Vec::new();
+    for path in &files {
+        let compressed = fs::read(path).unwrap();
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        let original = fs::read(original_p).unwrap();
+
+        let mut hasher = twox_hash::XxHash64::with_seed(0);
+        hasher.write(&original);
+        table.extend((compressed.len() as u32).to_le_bytes());
+        table.extend((original.len() as u32).to_le_bytes());
+        table.extend((hasher.finish() as u32).to_le_bytes());
+
+        source.extend(compressed);
+        expected.extend(original);
+    }
+    table.extend((files.len() as u32).to_le_bytes());
+    table.push(0x80);
+    table.extend(crate::seekable::SEEKABLE_MAGIC_NUM.to_le_bytes());
+    source.extend(crate::seekable::SEEK_TABLE_FRAME_MAGIC_NUM.to_le_bytes());
+    source.extend((table.len() as u32).to_le_bytes());
+    source.extend(table);
+
+    let mut decoder = SeekableDecoder::new(Cursor::new(source.as_slice())).unwrap();
+    decoder.set_cache_size(2);
+    assert_eq!(decoder.seek_table().entries.len(), files.len());
+    assert_eq!(decoder.decompressed_size(), expected.len() as u64);
+
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    assert!(result == expected, "Sequential read differs");
+
+    // random access in both directions, across frame boundaries
+    let len = expected.len() as u64;
+    for &start in &[len / 2, 0, len - 100, len / 3, 7, len / 2 + 1] {
+        decoder.seek(SeekFrom::Start(start)).unwrap();
+        let mut buf = vec![0; 100_000];
+        let amount = usize::min(buf.len(), (len - start) as usize);
+        decoder.read_exact(&mut buf[..amount]).unwrap();
+        assert!(
+            buf[..amount] == expected[start as usize..start as usize + amount],
+            "Read at {} differs",
+            start
+        );
+    }
+
+    decoder.seek(SeekFrom::End(-10)).unwrap();
+    let mut tail = Vec::new();
+    decoder.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &expected[expected.len() - 10..]);
+    assert!(decoder.seek(SeekFrom::Current(-(len as i64) - 1)).is_err());
+
+    // corrupted seek table checksum must be detected
+    let mut corrupted = source.clone();
+    let first_checksum = corrupted.len() - 9 - files.len() * 12 + 8;
+    corrupted[first_checksum] ^= 1;
+    let mut decoder = SeekableDecoder::new(Cursor::new(corrupted)).unwrap();
+    let mut buf = [0u8; 1];
+    assert!(!expected.is_empty());
+    assert!(decoder.read(&mut buf).is_err());
+
+    // a compressed size that does not match the frame must be detected, here the first frame is said to be one
+    // byte longer and the second one byte shorter, so all offsets after them stay the same
+    let mut corrupted = source.clone();
+    let first_entry = corrupted.len() - 9 - files.len() * 12;
+    for (entry, delta) in [(first_entry, 1i64), (first_entry + 12, -1)] {
+        let size =
+            crate::decoding::little_endian::read_little_endian_u32(&corrupted[entry..entry + 4]);
+        let size = (i64::from(size) + delta) as u32;
+        corrupted[entry..entry + 4].copy_from_slice(&size.to_le_bytes());
+    }
+    let mut decoder = SeekableDecoder::new(Cursor::new(corrupted)).unwrap();
+    let err = decoder.read(&mut buf).unwrap_err();
+    assert!(err.to_string().contains("bytes long"), "{}", err);
+
+    // a file without a seek table is rejected
+    assert!(SeekableDecoder::new(Cursor::new(fs::read(&files[0]).unwrap())).is_err());
+}
+
+#[test]
+fn test_seekable_encoder_roundtrip() {
+    use crate::seekable::{SeekableDecoder, SeekableEncoder, SEEK_TABLE_FRAME_MAGIC_NUM};
+    use std::fs;
+    use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| !p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(10);
+
+    let mut original = Vec::new();
+    for path in &files {
+        original.extend(fs::read(path).unwrap());
+    }
+    // long runs of one byte end up as RLE blocks
+    original.extend(vec![7u8; 300_000]);
+
+    for &(max_frame_size, checksums) in &[(1000, true), (200_000, true), (u32::MAX, false)] {
+        let mut encoder = SeekableEncoder::new(Vec::new(), max_frame_size);
+        encoder.set_checksums(checksums);
+        // uneven writes to check that frames are cut at the right size
+        for chunk in original.chunks(777) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let archive = encoder.finish().unwrap();
+
+        // the archive is also plain zstd that the normal decoder can read up to the seek table
+        let mut plain = Vec::new();
+        let mut source = archive.as_slice();
+        while crate::decoding::little_endian::read_little_endian_u32(&source[..4])
+            != SEEK_TABLE_FRAME_MAGIC_NUM
+        {
+            let mut dec = crate::streaming_decoder::StreamingDecoder::new(&mut source).unwrap();
+            dec.read_to_end(&mut plain).unwrap();
+        }
+        assert!(plain == original, "Plain decoding differs");
+
+        let mut decoder = SeekableDecoder::new(Cursor::new(archive)).unwrap();
+        assert_eq!(decoder.seek_table().has_checksums, checksums);
+        assert_eq!(decoder.decompressed_size(), original.len() as u64);
+        for entry in &decoder.seek_table().entries {
+            assert!(entry.decompressed_size <= max_frame_size);
+        }
+
+        let start = original.len() as u64 / 3;
+        decoder.seek(SeekFrom::Start(start)).unwrap();
+        let mut result = Vec::new();
+        decoder.read_to_end(&mut result).unwrap();
+        assert!(
+            result == original[start as usize..],
+            "Seekable decoding differs"
+        );
+    }
+
+    // one frame per byte
+    let mut encoder = SeekableEncoder::new(Vec::new(), 1);
+    encoder.write_all(&original[..300]).unwrap();
+    let mut decoder = SeekableDecoder::new(Cursor::new(encoder.finish().unwrap())).unwrap();
+    assert_eq!(decoder.seek_table().entries.len(), 300);
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    assert_eq!(result, &original[..300]);
+
+    // nothing written still results in a valid (empty) seekable archive
+    let archive = SeekableEncoder::new(Vec::new(), 100).finish().unwrap();
+    let mut decoder = SeekableDecoder::new(Cursor::new(archive)).unwrap();
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_seekable_reference_fixture() {
+    use crate::seekable::SeekableDecoder;
+    use std::fs;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    // made with libzstd 1.5.7 like ZSTD_seekable_compressStream in zstd/contrib/seekable_format does it: level 3,
+    // frames with checksums ended every 32768 bytes, seek table with checksums. The input is the first 100000
+    // bytes of decodecorpus_files/z000000
+    let archive = fs::read("./seekable_tests/z000000_100k.seekable.zst").unwrap();
+    let expected = fs::read("./decodecorpus_files/z000000").unwrap()[..100_000].to_vec();
+
+    let mut decoder = SeekableDecoder::new(Cursor::new(archive.as_slice())).unwrap();
+    let table = decoder.seek_table();
+    assert!(table.has_checksums);
+    let sizes: Vec<_> = table.entries.iter().map(|e| e.decompressed_size).collect();
+    assert_eq!(sizes, [32768, 32768, 32768, 1696]);
+    assert_eq!(
+        table.compressed_size() as usize,
+        archive.len() - 8 - 4 * 12 - 9
+    );
+
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    assert!(result == expected, "Sequential read differs");
+
+    decoder.seek(SeekFrom::Start(40_000)).unwrap();
+    let mut buf = vec![0; 30_000];
+    decoder.read_exact(&mut buf).unwrap();
+    assert!(buf == expected[40_000..70_000], "Read after seek differs");
+}
+
+#[test]
+fn test_seekable_encoder_table_layout() {
+    use crate::inspect::{self, InspectedFrame};
+    use crate::seekable::SeekableEncoder;
+    use std::hash::Hasher;
+    use std::io::Write;
+
+    let data: Vec<u8> = (0..2500u32).map(|x| (x % 251) as u8).collect();
+    for checksums in [true, false] {
+        let mut encoder = SeekableEncoder::new(Vec::new(), 1000);
+        encoder.set_checksums(checksums);
+        encoder.write_all(&data).unwrap();
+        let archive = encoder.finish().unwrap();
+
+        // the seek table as the spec describes it, with the magic numbers spelled out instead of taken from the crate
+        let frames = inspect::inspect_slice(&archive, false).unwrap();
+        let mut entries = Vec::new();
+        let mut frames_size = 0;
+        for (frame, content) in frames.iter().zip(data.chunks(1000)) {
+            let InspectedFrame::Zstd(frame) = frame else {
+                continue;
+            };
+            entries.extend((frame.compressed_size as u32).to_le_bytes());
+            entries.extend((content.len() as u32).to_le_bytes());
+            if checksums {
+                let mut hasher = twox_hash::XxHash64::with_seed(0);
+                hasher.write(content);
+                entries.extend((hasher.finish() as u32).to_le_bytes());
+            }
+            frames_size += frame.compressed_size as usize;
+        }
+        assert_eq!(frames.len(), 4);
+        assert!(matches!(frames[3], InspectedFrame::Skippable(_)));
+
+        let mut expected = vec![0x5E, 0x2A, 0x4D, 0x18];
+        expected.extend((entries.len() as u32 + 9).to_le_bytes());
+        expected.extend(entries);
+        expected.extend(3u32.to_le_bytes());
+        expected.push(if checksums { 0x80 } else { 0 });
+        expected.extend([0xB1, 0xEA, 0x92, 0x8F]);
+        assert_eq!(archive[frames_size..], expected[..]);
+    }
+}
@@ -0,0 +1,64 @@
+#[test]
+fn test_block_timings() {
+    use crate::block::block::BlockType;
+    use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+    use crate::inspect::{self, InspectedFrame};
+    use std::fs;
+
+    let mut frame_dec = FrameDecoder::new();
+    assert_eq!(frame_dec.block_timings(), None);
+    frame_dec.set_block_timings(true);
+
+    let mut expected_blocks = [0u64; 3];
+    let mut decoded_len = 0;
+    for name in ["z000033.zst", "z000088.zst", "z000099.zst"].iter() {
+        let compressed = fs::read(format!("./decodecorpus_files/{}", name)).unwrap();
+        for frame in inspect::inspect_slice(&compressed, true).unwrap() {
+            if let InspectedFrame::Zstd(info) = frame {
+                for block in info.blocks {
+                    match block.block_type {
+                        BlockType::Raw => expected_blocks[0] += 1,
+                        BlockType::RLE => expected_blocks[1] += 1,
+                        BlockType::Compressed => expected_blocks[2] += 1,
+                        BlockType::Reserved => unreachable!(),
+                    }
+                }
+            }
+        }
+
+        let mut source = compressed.as_slice();
+        frame_dec.reset(&mut source).unwrap();
+        frame_dec
+            .decode_blocks(&mut source, BlockDecodingStrategy::All)
+            .unwrap();
+        decoded_len += frame_dec.collect().unwrap().len() as u64;
+    }
+
+    let timings = frame_dec.block_timings().unwrap();
+    assert_eq!(
+        [
+            timings.raw.blocks,
+            timings.rle.blocks,
+            timings.compressed.blocks
+        ],
+        expected_blocks
+    );
+    assert_eq!(
+        timings.raw.decompressed_bytes
+            + timings.rle.decompressed_bytes
+            + timings.compressed.decompressed_bytes,
+        decoded_len
+    );
+    assert!(
+        timings.literals_decoding + timings.sequence_decoding + timings.sequence_execution
+            <= timings.compressed.time
+    );
+
+    frame_dec.set_block_timings(true);
+    assert_eq!(
+        frame_dec.block_timings().unwrap().total_time().as_nanos(),
+        0
+    );
+    frame_dec.set_block_timings(false);
+    assert_eq!(frame_dec.block_timings(), None);
+}
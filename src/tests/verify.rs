@@ -0,0 +1,97 @@
+#[test]
+fn test_verify_corpus_files() {
+    use crate::inspect::{self, InspectedFrame};
+    use crate::verify::{ChecksumStatus, VerifyErrorKind};
+    use std::fs;
+
+    let mut files: Vec<_> = fs::read_dir("./decodecorpus_files")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|p| p.to_str().unwrap().ends_with(".zst"))
+        .collect();
+    files.sort();
+    files.truncate(40);
+
+    let mut source = Vec::new();
+    let mut sizes = Vec::new();
+    let mut checksummed = None;
+    for (idx, path) in files.iter().enumerate() {
+        let compressed = fs::read(path).unwrap();
+        if let InspectedFrame::Zstd(info) = &inspect::inspect_slice(&compressed, false).unwrap()[0]
+        {
+            if info.checksum_flag && checksummed.is_none() {
+                checksummed = Some((idx, source.len() + compressed.len() - 1));
+            }
+        }
+        source.extend(compressed);
+        if idx == 3 {
+            source.extend(&[0x50, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+        }
+
+        let mut original_p = path.to_str().unwrap().to_owned();
+        original_p.truncate(original_p.len() - 4);
+        sizes.push(fs::metadata(original_p).unwrap().len());
+    }
+
+    let report = crate::verify(&mut source.as_slice()).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.skippable_frames, 1);
+    assert_eq!(report.frames.len(), files.len());
+    for (frame, size) in report.frames.iter().zip(&sizes) {
+        assert_eq!(frame.decompressed_size, *size);
+        assert_eq!(frame.has_checksum, frame.checksum == ChecksumStatus::Valid);
+    }
+    assert_eq!(
+        report.frames[4].offset,
+        report.frames[3].offset + report.frames[3].compressed_size + 11
+    );
+
+    // a wrong checksum is reported but the following frames are still checked
+    let (idx, checksum_byte) = checksummed.expect("Some corpus file has a checksum");
+    source[checksum_byte] ^= 1;
+    let report = crate::verify(&mut source.as_slice()).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.frames.len(), files.len());
+    assert!(matches!(
+        report.frames[idx].checksum,
+        ChecksumStatus::Mismatch { .. }
+    ));
+
+    // truncated input can not be verified
+    assert!(crate::verify(&mut &source[..source.len() - 2]).is_err());
+
+    // but verify_report keeps the frames before the error
+    let report = crate::verify::verify_report(
+        &mut &source[..source.len() - 2],
+        &mut crate::frame_decoder::FrameDecoder::new(),
+    );
+    assert!(!report.is_ok());
+    assert_eq!(report.frames.len(), files.len() - 1);
+    let error = report.error.unwrap();
+    assert_eq!(error.kind, VerifyErrorKind::InvalidFrame);
+    assert_eq!(error.frame_index, Some(files.len() - 1));
+    let last = report.frames.last().unwrap();
+    assert_eq!(error.offset, last.offset + last.compressed_size);
+}
+
+#[test]
+fn test_verify_missing_dict() {
+    use crate::frame_decoder::FrameDecoder;
+    use crate::verify::{verify_report, VerifyErrorKind};
+    use std::fs;
+
+    let compressed = fs::read("./dict_tests/files/ModemManager.service.zst").unwrap();
+    let report = verify_report(&mut compressed.as_slice(), &mut FrameDecoder::new());
+    let error = report.error.unwrap();
+    assert!(matches!(
+        error.kind,
+        VerifyErrorKind::DictNotProvided { dict_id } if dict_id != 0
+    ));
+    assert_eq!(error.frame_index, Some(0));
+
+    let mut frame_dec = FrameDecoder::new();
+    frame_dec
+        .add_dict(&fs::read("./dict_tests/dictionary").unwrap())
+        .unwrap();
+    assert!(verify_report(&mut compressed.as_slice(), &mut frame_dec).is_ok());
+}
@@ -0,0 +1,219 @@
+use crate::errors::FrameDecoderError;
+use crate::frame::{read_frame_start, skip_skippable_data, FrameStart, FrameStartError};
+use crate::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+use std::io::Read;
+
+/// How many bytes are decoded before they are fed to the hasher and dropped
+const BATCH_SIZE: usize = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The frame has no checksum, it could only be checked that it decodes
+    NotPresent,
+    Valid,
+    Mismatch {
+        from_data: u32,
+        calculated: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameReport {
+    /// Byte offset of the frame in the input
+    pub offset: u64,
+    pub compressed_size: u64,
+    pub decompressed_size: u64,
+    pub has_checksum: bool,
+    pub checksum: ChecksumStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyErrorKind {
+    /// Reading from the source failed
+    Io,
+    /// The input ends with bytes that are too short to be a frame, or inside a skippable frame
+    Truncated,
+    /// A frame could not be decoded, because it is corrupted or cut off
+    InvalidFrame,
+    /// A frame needs a dictionary the decoder does not have
+    DictNotProvided { dict_id: u32 },
+}
+
+/// Why verifying stopped, see verify_report()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    pub kind: VerifyErrorKind,
+    /// Byte offset of the frame (or trailing bytes) that could not be verified
+    pub offset: u64,
+    /// Index of the zstd frame that failed, None if the error is not inside a zstd frame
+    pub frame_index: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub frames: Vec<FrameReport>,
+    pub skippable_frames: usize,
+    /// Only set by verify_report(), verify() returns the error instead
+    pub error: Option<VerifyError>,
+}
+
+impl VerifyReport {
+    /// True if no frame had a checksum mismatch and nothing failed
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+            && self
+                .frames
+                .iter()
+                .all(|f| !matches!(f.checksum, ChecksumStatus::Mismatch { .. }))
+    }
+
+    pub fn decompressed_size(&self) -> u64 {
+        self.frames.iter().map(|f| f.decompressed_size).sum()
+    }
+}
+
+/// Decodes all frames in source and checks their checksums, like zstd -t. The output is only fed into the
+/// checksum calculation and then dropped, at most the window of one frame is kept in memory.
+///
+/// Frames that decode but have a wrong checksum are reported in the VerifyReport. Frames that can not be decoded
+/// at all make this return an error because nothing after them can be checked.
+pub fn verify(source: &mut dyn Read) -> Result<VerifyReport, String> {
+    verify_with_decoder(source, &mut FrameDecoder::new())
+}
+
+/// Like verify() but uses the provided decoder, e.g. one that has dicts added
+pub fn verify_with_decoder(
+    source: &mut dyn Read,
+    frame_dec: &mut FrameDecoder,
+) -> Result<VerifyReport, String> {
+    let report = verify_report(source, frame_dec);
+    match report.error {
+        None => Ok(report),
+        Some(error) => match error.frame_index {
+            Some(idx) => Err(format!(
+                "Frame {} at offset {}: {}",
+                idx, error.offset, error.message
+            )),
+            None => Err(error.message),
+        },
+    }
+}
+
+/// Like verify_with_decoder() but an error does not discard the frames that were checked before it. The error is
+/// stored in the report instead, with a kind that can be reported without parsing the message.
+pub fn verify_report(source: &mut dyn Read, frame_dec: &mut FrameDecoder) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    if let Err(error) = verify_frames(source, frame_dec, &mut report) {
+        report.error = Some(error);
+    }
+    report
+}
+
+fn verify_frames(
+    source: &mut dyn Read,
+    frame_dec: &mut FrameDecoder,
+    report: &mut VerifyReport,
+) -> Result<(), VerifyError> {
+    let error = |kind, offset, frame_index, message| VerifyError {
+        kind,
+        offset,
+        frame_index,
+        message,
+    };
+    let mut offset = 0;
+
+    loop {
+        let magic = match read_frame_start(source) {
+            Ok(FrameStart::End) => return Ok(()),
+            Ok(FrameStart::Frame { magic }) => magic,
+            Ok(FrameStart::Skippable { data_size, .. }) => {
+                let skipped = skip_skippable_data(source, data_size).map_err(|e| {
+                    let message = format!("Error while skipping frame at offset {}: {}", offset, e);
+                    error(VerifyErrorKind::Io, offset, None, message)
+                })?;
+                if skipped != u64::from(data_size) {
+                    let message = format!(
+                        "Skippable frame at offset {} says it is {} bytes long but there are only {} bytes left",
+                        offset, data_size, skipped
+                    );
+                    return Err(error(VerifyErrorKind::Truncated, offset, None, message));
+                }
+                report.skippable_frames += 1;
+                offset += 8 + u64::from(data_size);
+                continue;
+            }
+            Err(FrameStartError::Io(e)) => {
+                let message = format!("Error while reading at offset {}: {}", offset, e);
+                return Err(error(VerifyErrorKind::Io, offset, None, message));
+            }
+            Err(FrameStartError::TrailingBytes(x)) => {
+                let message = format!(
+                    "Trailing {} bytes at offset {} are too short to be a frame",
+                    x, offset
+                );
+                return Err(error(VerifyErrorKind::Truncated, offset, None, message));
+            }
+            Err(FrameStartError::TruncatedSkippableHeader) => {
+                let message = format!("Skippable frame at offset {} is missing its size", offset);
+                return Err(error(VerifyErrorKind::Truncated, offset, None, message));
+            }
+        };
+
+        let frame = verify_frame(source, magic, frame_dec, offset)
+            .map_err(|(kind, message)| error(kind, offset, Some(report.frames.len()), message))?;
+        offset += frame.compressed_size;
+        report.frames.push(frame);
+    }
+}
+
+fn verify_frame(
+    source: &mut dyn Read,
+    magic: [u8; 4],
+    frame_dec: &mut FrameDecoder,
+    offset: u64,
+) -> Result<FrameReport, (VerifyErrorKind, String)> {
+    let invalid = |m: String| (VerifyErrorKind::InvalidFrame, m);
+    let io = |e: std::io::Error| (VerifyErrorKind::Io, e.to_string());
+    frame_dec
+        .reset(&mut (&magic[..]).chain(&mut *source))
+        .map_err(invalid)?;
+
+    let mut sink = std::io::sink();
+    let mut decompressed_size = 0;
+    while !frame_dec.is_finished() {
+        frame_dec
+            .decode_blocks(source, BlockDecodingStrategy::UptoBytes(BATCH_SIZE))
+            .map_err(|e| match e {
+                FrameDecoderError::DictNotProvided { dict_id } => {
+                    (VerifyErrorKind::DictNotProvided { dict_id }, e.to_string())
+                }
+                e => invalid(e.to_string()),
+            })?;
+        decompressed_size += frame_dec.collect_to_writer(&mut sink).map_err(io)? as u64;
+    }
+    decompressed_size += frame_dec.collect_to_writer(&mut sink).map_err(io)? as u64;
+
+    let checksum = match frame_dec.get_checksum_from_data() {
+        None => ChecksumStatus::NotPresent,
+        Some(from_data) => {
+            let calculated = frame_dec.get_calculated_checksum().unwrap_or(0);
+            if from_data == calculated {
+                ChecksumStatus::Valid
+            } else {
+                ChecksumStatus::Mismatch {
+                    from_data,
+                    calculated,
+                }
+            }
+        }
+    };
+
+    Ok(FrameReport {
+        offset,
+        compressed_size: frame_dec.bytes_read_from_source(),
+        decompressed_size,
+        has_checksum: checksum != ChecksumStatus::NotPresent,
+        checksum,
+    })
+}